@@ -30,13 +30,23 @@ L = Literal[
     "fi",
     "fr",
     "grc",
+    "id",
     "is",
+    "it",
     "ja",
     "ko",
+    "ku",
     "la",
+    "ms",
+    "nl",
+    "pl",
+    "pt",
     "ru",
+    "simple",
     "sq",
     "th",
+    "tr",
+    "vi",
     "zh",
 ]
 """A language code that appears in the testsuite."""
@@ -1,10 +1,16 @@
 use anyhow::{Ok, Result, bail};
 use clap::{Parser, Subcommand};
 use std::path::PathBuf;
+use std::sync::Arc;
 
+use crate::config::Config;
+use crate::dict::install_rules;
+use crate::filter::Expr;
+use crate::freq::{FreqList, FreqMode};
 use crate::lang::Edition;
 use crate::lang::{EditionLang, Lang};
-use crate::models::kaikki::WordEntry;
+use crate::models::kaikki::{ExamplePreference, LinkageKind, WordEntry};
+use crate::path::DictionaryType;
 
 #[derive(Debug, Parser)]
 #[command(version)]
@@ -36,11 +42,29 @@ pub enum Command {
     /// Phonetic transcription dictionary. Uses all editions
     IpaMerged(IpaMergedArgs),
 
+    /// Make several dictionaries, reading every jsonlines only once
+    Batch(BatchArgs),
+
+    /// Pitch accent dictionary. Uses target for the edition
+    Pitch(MainArgs),
+
+    /// Frequency dictionary made from a frequency list (--freq). Uses target for the edition
+    Freq(MainArgs),
+
     /// Download a Kaikki jsonlines
     Download(MainArgs),
 
     /// Show supported iso codes, with coloured editions
     Iso(IsoArgs),
+
+    /// Check a dictionary against the yomitan schemas
+    Validate(ValidateArgs),
+
+    /// Look up a word in a dictionary, following inflected forms to their lemmas
+    Lookup(LookupArgs),
+
+    /// Compare two builds of a dictionary
+    Diff(DiffArgs),
 }
 
 #[derive(Parser, Debug, Default)]
@@ -108,6 +132,27 @@ pub struct IpaMergedArgs {
     pub options: Options,
 }
 
+#[derive(Parser, Debug, Default)]
+pub struct BatchArgs {
+    // Example:
+    //   `kty batch main,de,en glossary,de,fr ipa,de,en ipa-merged,de`
+    //
+    /// Dictionaries to make, as comma-separated type,source,target (ipa-merged: type,target)
+    #[arg(required = true, value_parser = parse_dict_spec)]
+    pub dicts: Vec<DictSpec>,
+
+    /// Dictionary name
+    #[arg(long, default_value = "kty")]
+    pub dict_name: String,
+
+    #[command(flatten)]
+    pub options: Options,
+
+    /// One validated command per dictionary, filled by `prepare_command`.
+    #[arg(skip)]
+    pub commands: Vec<Command>,
+}
+
 #[derive(Parser, Debug, Default)]
 pub struct IsoArgs {
     /// Only print languages with edition
@@ -115,6 +160,40 @@ pub struct IsoArgs {
     pub edition: bool,
 }
 
+#[derive(Parser, Debug, Default)]
+pub struct ValidateArgs {
+    /// Zipped dictionary, or directory with its banks (f.e. the temporary files of --save-temps)
+    #[arg(value_name = "ZIP|DIR")]
+    pub path: PathBuf,
+}
+
+#[derive(Parser, Debug, Default)]
+pub struct LookupArgs {
+    /// Zipped dictionary, or directory with its banks (f.e. the temporary files of --save-temps)
+    #[arg(value_name = "ZIP|DIR")]
+    pub path: PathBuf,
+
+    /// Word to look up
+    pub word: String,
+
+    /// Other dictionaries to look up the word in (f.e. an ipa dictionary)
+    #[arg(long = "with", value_name = "ZIP|DIR")]
+    pub others: Vec<PathBuf>,
+}
+
+#[derive(Parser, Debug, Default)]
+pub struct DiffArgs {
+    /// Old dictionary, zipped or a directory with its banks
+    pub old: PathBuf,
+
+    /// New dictionary, zipped or a directory with its banks
+    pub new: PathBuf,
+
+    /// Show what changed for every term, and not only a summary
+    #[arg(long)]
+    pub details: bool,
+}
+
 /// Langs-like struct that validates edition for `target` and skips `edition`.
 #[derive(Parser, Debug, Default)]
 pub struct MainLangs {
@@ -172,7 +251,7 @@ pub struct IpaMergedLangs {
 }
 
 #[expect(clippy::struct_excessive_bools)]
-#[derive(Parser, Debug, Default)]
+#[derive(Parser, Debug, Default, Clone)]
 pub struct Options {
     /// Write temporary files to disk and skip zipping
     #[arg(long, short)]
@@ -182,6 +261,15 @@ pub struct Options {
     #[arg(long, short)]
     pub redownload: bool,
 
+    /// Read the jsonlines from this path (- for stdin) instead of looking it up in the root
+    /// directory
+    #[arg(long, short, value_name = "PATH|-")]
+    pub input: Option<PathBuf>,
+
+    /// Keep the downloaded .jsonl.gz instead of extracting it. It is decompressed on the fly
+    #[arg(long)]
+    pub keep_compressed: bool,
+
     /// Only keep the first n filtered lines. -1 keeps all
     #[arg(long, default_value_t = -1)]
     pub first: i32,
@@ -206,6 +294,15 @@ pub struct Options {
     #[arg(long, value_parser = parse_tuple)]
     pub reject: Vec<(FilterKey, String)>,
 
+    // Example:
+    //   `--where 'pos in [noun, verb] && senses.topics == medicine'`
+    //
+    // You can specify this option multiple times, in which case entries must match all of them.
+    //
+    /// Only keep entries matching a filter expression on any field (cf. docs)
+    #[arg(long = "where", value_name = "EXPR")]
+    pub where_exprs: Vec<Expr>,
+
     /// Do not print anything to the console
     #[arg(long, short)]
     pub quiet: bool,
@@ -218,6 +315,15 @@ pub struct Options {
     #[arg(long)]
     pub skip_yomitan: bool,
 
+    /// Skip checking the dictionary against the yomitan schemas once it is written
+    #[arg(long)]
+    pub skip_validation: bool,
+
+    /// Cache the processed lines, so that the next --incremental run only parses and preprocesses
+    /// the lines that changed
+    #[arg(long)]
+    pub incremental: bool,
+
     /// Include experimental features
     #[arg(short, long)]
     pub experimental: bool,
@@ -225,6 +331,78 @@ pub struct Options {
     /// Change the root directory
     #[arg(long, default_value = "data")]
     pub root_dir: PathBuf,
+
+    /// Number of worker threads used to process the jsonlines. 0 uses all available cores
+    #[arg(long, short, default_value_t = 0)]
+    pub jobs: usize,
+
+    /// Frequency list (TSV of word and count or rank) used to score entries
+    #[arg(long, value_name = "PATH")]
+    pub freq: Option<PathBuf>,
+
+    /// Whether the frequency list has counts or ranks
+    #[arg(long, value_enum, default_value_t = FreqMode::Count)]
+    pub freq_mode: FreqMode,
+
+    /// Show usage notes and sense qualifiers (main dictionary)
+    #[arg(long)]
+    pub notes: bool,
+
+    /// Related words shown after the glosses, as a comma-separated list (main dictionary)
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KINDS")]
+    pub linkages: Vec<LinkageKind>,
+
+    /// Maximum number of examples per sense. Keeps all by default (main dictionary)
+    #[arg(long, value_name = "N")]
+    pub max_examples: Option<usize>,
+
+    /// Maximum length of an example, in characters. Defaults to 120 (main dictionary)
+    #[arg(long, value_name = "N")]
+    pub max_example_length: Option<usize>,
+
+    /// Which examples come first, and survive --max-examples (main dictionary)
+    #[arg(long, value_enum, default_value_t = ExamplePreference::Translated)]
+    pub prefer_examples: ExamplePreference,
+
+    /// TOML config file with output paths and index templates
+    #[arg(long = "config", value_name = "PATH")]
+    pub config_path: Option<PathBuf>,
+
+    /// TOML file overriding the bundled edition rules (deinflection patterns, readings...)
+    #[arg(long, value_name = "PATH")]
+    pub rules: Option<PathBuf>,
+
+    /// The loaded config, filled by `prepare_command`.
+    #[arg(skip)]
+    pub config: Option<Config>,
+
+    /// The loaded frequency list, filled by `prepare_command`.
+    #[arg(skip)]
+    pub freq_list: Option<Arc<FreqList>>,
+}
+
+impl Options {
+    /// Number of workers to spawn, resolving `jobs = 0` to the available parallelism.
+    pub fn worker_count(&self) -> usize {
+        match self.jobs {
+            0 => std::thread::available_parallelism().map_or(1, std::num::NonZero::get),
+            n => n,
+        }
+    }
+
+    /// The linkages to deserialize: those of `--linkages`, and those that `--where` looks at.
+    pub fn kept_linkages(&self) -> Vec<LinkageKind> {
+        LinkageKind::ALL
+            .into_iter()
+            .filter(|kind| {
+                self.linkages.contains(kind)
+                    || self
+                        .where_exprs
+                        .iter()
+                        .any(|expr| expr.mentions(kind.field()))
+            })
+            .collect()
+    }
 }
 
 fn parse_tuple(s: &str) -> Result<(FilterKey, String), String> {
@@ -262,6 +440,119 @@ impl FilterKey {
     }
 }
 
+/// A dictionary of a batch. Cf. `BatchArgs`.
+#[derive(Debug, Clone)]
+pub struct DictSpec {
+    pub ty: DictionaryType,
+    pub source: Lang,
+    pub target: Lang,
+}
+
+fn parse_dict_spec(s: &str) -> Result<DictSpec, String> {
+    let parts: Vec<_> = s.split(',').map(str::trim).collect();
+    let ty = match parts[0] {
+        "main" => DictionaryType::Main,
+        "glossary" => DictionaryType::Glossary,
+        "ipa" => DictionaryType::Ipa,
+        "ipa-merged" => DictionaryType::IpaMerged,
+        "pitch" => DictionaryType::Pitch,
+        "freq" => DictionaryType::Freq,
+        other => {
+            return Err(format!(
+                "unknown dictionary type '{other}'. Choose between: main | glossary | ipa | ipa-merged | pitch | freq"
+            ));
+        }
+    };
+    let (source, target) = match (ty, &parts[1..]) {
+        (DictionaryType::IpaMerged, [target]) => (*target, *target),
+        (DictionaryType::IpaMerged, _) => return Err("expected ipa-merged,target".into()),
+        (_, [source, target]) => (*source, *target),
+        _ => return Err("expected three comma-separated values: type,source,target".into()),
+    };
+    core::result::Result::Ok(DictSpec {
+        ty,
+        source: source.parse()?,
+        target: target.parse()?,
+    })
+}
+
+impl DictSpec {
+    /// Build the command that would make this dictionary on its own.
+    fn to_command(&self, dict_name: &str, options: &Options) -> Result<Command> {
+        let edition_of = |lang: Lang| -> Result<EditionLang> {
+            EditionLang::try_from(lang).map_err(|e| anyhow::anyhow!("{lang}: {e}"))
+        };
+        let dict_name = dict_name.to_string();
+        let mut options = options.clone();
+        // Options are shared by the whole batch, but only these dictionaries use --freq
+        if !matches!(self.ty, DictionaryType::Main | DictionaryType::Freq) {
+            options.freq = None;
+            options.freq_list = None;
+        }
+
+        let cmd = match self.ty {
+            DictionaryType::Main => Command::Main(MainArgs {
+                langs: MainLangs {
+                    edition: EditionLang::default(),
+                    source: self.source,
+                    target: edition_of(self.target)?,
+                },
+                dict_name,
+                options,
+            }),
+            DictionaryType::Glossary => Command::Glossary(GlossaryArgs {
+                langs: GlossaryLangs {
+                    edition: EditionLang::default(),
+                    source: edition_of(self.source)?,
+                    target: self.target,
+                },
+                dict_name,
+                options,
+            }),
+            DictionaryType::Ipa => Command::Ipa(IpaArgs {
+                langs: MainLangs {
+                    edition: EditionLang::default(),
+                    source: self.source,
+                    target: edition_of(self.target)?,
+                },
+                dict_name,
+                options,
+            }),
+            DictionaryType::IpaMerged => Command::IpaMerged(IpaMergedArgs {
+                langs: IpaMergedLangs {
+                    target: self.target,
+                    ..Default::default()
+                },
+                dict_name,
+                options,
+            }),
+            DictionaryType::Pitch => Command::Pitch(MainArgs {
+                langs: MainLangs {
+                    edition: EditionLang::default(),
+                    source: self.source,
+                    target: edition_of(self.target)?,
+                },
+                dict_name,
+                options,
+            }),
+            DictionaryType::Freq => Command::Freq(MainArgs {
+                langs: MainLangs {
+                    edition: EditionLang::default(),
+                    source: self.source,
+                    target: edition_of(self.target)?,
+                },
+                dict_name,
+                options,
+            }),
+            DictionaryType::GlossaryExtended => {
+                bail!("glossary-extended is not supported in batches")
+            }
+        };
+
+        Ok(cmd)
+    }
+}
+
 fn push_filter_key_lang(filter: &mut Vec<(FilterKey, String)>, lang: Lang) {
     filter.push((FilterKey::LangCode, lang.to_string()));
 }
@@ -289,15 +580,77 @@ fn err_on_simple_english<A: SimpleArgs>(args: &A) -> Result<()> {
     Ok(())
 }
 
+// Only the main and freq dictionaries read the frequency list
+fn err_on_freq(options: &Options) -> Result<()> {
+    anyhow::ensure!(
+        options.freq.is_none(),
+        "--freq is only used by the main and freq dictionaries."
+    );
+    Ok(())
+}
+
+fn load_config(options: &mut Options) -> Result<()> {
+    if let (Some(path), None) = (&options.config_path, &options.config) {
+        options.config = Some(Config::load(path)?);
+    }
+    Ok(())
+}
+
+fn load_rules(options: &Options) -> Result<()> {
+    if let Some(path) = &options.rules {
+        install_rules(path)?;
+    }
+    Ok(())
+}
+
+fn load_freq_list(options: &mut Options) -> Result<()> {
+    if let (Some(path), None) = (&options.freq, &options.freq_list) {
+        options.freq_list = Some(Arc::new(FreqList::load(path, options.freq_mode)?));
+    }
+    Ok(())
+}
+
+impl Command {
+    const fn options_mut(&mut self) -> Option<&mut Options> {
+        match self {
+            Self::Main(args) | Self::Pitch(args) | Self::Freq(args) | Self::Download(args) => {
+                Some(&mut args.options)
+            }
+            Self::Glossary(args) => Some(&mut args.options),
+            Self::GlossaryExtended(args) => Some(&mut args.options),
+            Self::Ipa(args) => Some(&mut args.options),
+            Self::IpaMerged(args) => Some(&mut args.options),
+            Self::Batch(args) => Some(&mut args.options),
+            Self::Iso(_) | Self::Validate(_) | Self::Lookup(_) | Self::Diff(_) => None,
+        }
+    }
+}
+
+/// An input replaces a single jsonlines, and stdin can only be read once.
+fn err_on_multiple_editions_input(edition: Edition, options: &Options) -> Result<()> {
+    anyhow::ensure!(
+        options.input.is_none() || matches!(edition, Edition::EditionLang(_)),
+        "--input requires a single edition, but this dictionary reads all of them."
+    );
+    Ok(())
+}
+
 fn prepare_command(cmd: &mut Command) -> Result<()> {
+    if let Some(options) = cmd.options_mut() {
+        load_config(options)?;
+        load_rules(options)?;
+    }
+
     match cmd {
         Command::Main(args) => {
             check_simple_english(args)?;
             args.langs.edition = args.langs.target;
             push_filter_key_lang(&mut args.options.filter, args.langs.source);
+            load_freq_list(&mut args.options)?;
         }
         Command::Glossary(args) => {
             err_on_simple_english(args)?;
+            err_on_freq(&args.options)?;
             let source_as_lang: Lang = args.langs.source.into();
             anyhow::ensure!(
                 source_as_lang != args.langs.target,
@@ -309,26 +662,66 @@ fn prepare_command(cmd: &mut Command) -> Result<()> {
         }
         Command::GlossaryExtended(args) => {
             err_on_simple_english(args)?;
+            err_on_freq(&args.options)?;
             anyhow::ensure!(
                 args.langs.source != args.langs.target,
                 "in a glossary dictionary source must be different from target."
             );
+            err_on_multiple_editions_input(args.langs.edition, &args.options)?;
         }
         Command::Ipa(args) => {
             err_on_simple_english(args)?;
+            err_on_freq(&args.options)?;
             args.langs.edition = args.langs.target;
             push_filter_key_lang(&mut args.options.filter, args.langs.source);
         }
         Command::IpaMerged(args) => {
             err_on_simple_english(args)?;
+            err_on_freq(&args.options)?;
             args.langs.edition = Edition::All;
             args.langs.source = args.langs.target;
             push_filter_key_lang(&mut args.options.filter, args.langs.source);
+            err_on_multiple_editions_input(args.langs.edition, &args.options)?;
+        }
+        Command::Pitch(args) => {
+            err_on_simple_english(args)?;
+            err_on_freq(&args.options)?;
+            args.langs.edition = args.langs.target;
+            push_filter_key_lang(&mut args.options.filter, args.langs.source);
+        }
+        Command::Freq(args) => {
+            err_on_simple_english(args)?;
+            anyhow::ensure!(
+                args.options.freq.is_some(),
+                "a frequency dictionary requires a frequency list (--freq)."
+            );
+            args.langs.edition = args.langs.target;
+            push_filter_key_lang(&mut args.options.filter, args.langs.source);
+            load_freq_list(&mut args.options)?;
+        }
+        Command::Batch(args) => {
+            anyhow::ensure!(
+                args.options.input.is_none(),
+                "--input is not supported in batches."
+            );
+            anyhow::ensure!(
+                !args.options.incremental,
+                "--incremental is not supported in batches."
+            );
+            // Load it once for every dictionary
+            load_freq_list(&mut args.options)?;
+            let mut commands = Vec::with_capacity(args.dicts.len());
+            for spec in &args.dicts {
+                let mut cmd = spec.to_command(&args.dict_name, &args.options)?;
+                prepare_command(&mut cmd)?;
+                commands.push(cmd);
+            }
+            args.commands = commands;
         }
         Command::Download(args) => {
             args.langs.edition = args.langs.target;
         }
-        Command::Iso(_) => (),
+        Command::Iso(_) | Command::Validate(_) | Command::Lookup(_) | Command::Diff(_) => (),
     }
 
     Ok(())
@@ -451,10 +844,85 @@ mod tests {
         assert!(prepare_command(&mut cli.command).is_err());
     }
 
+    #[test]
+    fn batch_specs() {
+        assert!(Cli::try_parse_from(["kty", "batch", "main,de,en", "ipa-merged,de"]).is_ok());
+        assert!(Cli::try_parse_from(["kty", "batch", "main,de"]).is_err());
+        assert!(Cli::try_parse_from(["kty", "batch", "foo,de,en"]).is_err());
+        assert!(Cli::try_parse_from(["kty", "batch"]).is_err());
+    }
+
+    #[test]
+    fn batch_validates_every_dict() {
+        let mut cli =
+            Cli::try_parse_from(["kty", "batch", "main,de,en", "glossary,el,el"]).unwrap();
+        assert!(prepare_command(&mut cli.command).is_err());
+
+        let mut cli =
+            Cli::try_parse_from(["kty", "batch", "main,de,en", "glossary,de,fr"]).unwrap();
+        prepare_command(&mut cli.command).unwrap();
+        let Command::Batch(args) = cli.command else {
+            unreachable!()
+        };
+        assert_eq!(args.commands.len(), 2);
+    }
+
+    #[test]
+    fn input_requires_single_edition() {
+        let mut cli = Cli::try_parse_from(["kty", "main", "de", "en", "--input", "-"]).unwrap();
+        assert!(prepare_command(&mut cli.command).is_ok());
+
+        let mut cli = Cli::try_parse_from(["kty", "ipa-merged", "de", "--input", "-"]).unwrap();
+        assert!(prepare_command(&mut cli.command).is_err());
+
+        let mut cli =
+            Cli::try_parse_from(["kty", "batch", "main,de,en", "--input", "foo.jsonl"]).unwrap();
+        assert!(prepare_command(&mut cli.command).is_err());
+    }
+
+    #[test]
+    fn freq_requires_a_dictionary_that_uses_it() {
+        for cmd in [
+            ["kty", "glossary", "de", "en"],
+            ["kty", "ipa", "de", "en"],
+            ["kty", "pitch", "ja", "en"],
+        ] {
+            let mut cli = Cli::try_parse_from(cmd.iter().chain(&["--freq", "de.tsv"])).unwrap();
+            assert!(prepare_command(&mut cli.command).is_err(), "{cmd:?}");
+        }
+    }
+
+    #[test]
+    fn incremental_requires_single_dictionary() {
+        let mut cli = Cli::try_parse_from(["kty", "main", "de", "en", "--incremental"]).unwrap();
+        assert!(prepare_command(&mut cli.command).is_ok());
+
+        let mut cli = Cli::try_parse_from(["kty", "batch", "main,de,en", "--incremental"]).unwrap();
+        assert!(prepare_command(&mut cli.command).is_err());
+    }
+
     #[test]
     fn filter_flag() {
         assert!(MainArgs::try_parse_from(["_pname", "el", "el", "--filter", "foo,bar"]).is_err());
         assert!(MainArgs::try_parse_from(["_pname", "el", "el", "--filter", "word,hello"]).is_ok());
         assert!(MainArgs::try_parse_from(["_pname", "el", "el", "--reject", "pos,name"]).is_ok());
     }
+
+    #[test]
+    fn where_flag() {
+        let args = MainArgs::try_parse_from([
+            "_pname",
+            "el",
+            "el",
+            "--where",
+            "pos in [noun, verb]",
+            "--where",
+            "senses.topics == medicine",
+        ])
+        .unwrap();
+        assert_eq!(args.options.where_exprs.len(), 2);
+        assert!(
+            MainArgs::try_parse_from(["_pname", "el", "el", "--where", "wrod == foo"]).is_err()
+        );
+    }
 }
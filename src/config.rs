@@ -0,0 +1,155 @@
+//! Configuration file (`--config`) to customize the output layout and the dictionary index.
+//!
+//! Example:
+//!
+//! ```toml
+//! [default]
+//! output_dir = "/srv/dicts/{target}/{source}"
+//! author = "My team"
+//! download_url = "https://example.com/{target}/{source}/{name}.zip"
+//!
+//! [glossary]
+//! zip_name = "{dict_name}-{source}-{target}-translations"
+//! ```
+//!
+//! Sections are named after the dictionary type (main, glossary, glossary-ext, ipa, ipa-merged,
+//! pitch, freq), and override the `[default]` section field by field.
+//!
+//! Every value is a template, with the placeholders: `{dict_name}`, `{edition}`, `{source}`,
+//! `{target}`, `{type}` and `{name}` (the default name, ex. `kty-de-en-gloss`).
+
+use anyhow::{Context, Ok, Result, bail};
+use serde::Deserialize;
+
+use std::path::Path;
+
+use crate::Map;
+
+/// Names of the sections, other than `[default]`. Cf. `DictionaryType` Display.
+const SECTIONS: [&str; 7] = [
+    "main",
+    "glossary",
+    "glossary-ext",
+    "ipa",
+    "ipa-merged",
+    "pitch",
+    "freq",
+];
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    default: DictConfig,
+
+    #[serde(flatten)]
+    sections: Map<String, DictConfig>,
+}
+
+/// Templates for a dictionary type. Unset values keep kty defaults.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DictConfig {
+    /// Directory of the dictionary, relative to the root directory.
+    pub output_dir: Option<String>,
+    /// Name of the zip, without extension.
+    pub zip_name: Option<String>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub attribution: Option<String>,
+    pub index_url: Option<String>,
+    pub download_url: Option<String>,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let config: Self = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config {}", path.display()))?;
+
+        for section in config.sections.keys() {
+            if !SECTIONS.contains(&section.as_str()) {
+                bail!(
+                    "Unknown section [{section}] in config {}. Choose between: default | {}",
+                    path.display(),
+                    SECTIONS.join(" | ")
+                );
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Return the templates of `section`, falling back to the `[default]` section.
+    pub fn section(&self, section: &str) -> DictConfig {
+        let mut merged = self.default.clone();
+        if let Some(overrides) = self.sections.get(section) {
+            merged.merge(overrides.clone());
+        }
+        merged
+    }
+}
+
+impl DictConfig {
+    fn merge(&mut self, other: Self) {
+        macro_rules! merge_fields {
+            ($($field:ident),*) => { $( if other.$field.is_some() { self.$field = other.$field; } )* };
+        }
+        merge_fields!(
+            output_dir,
+            zip_name,
+            title,
+            author,
+            description,
+            attribution,
+            index_url,
+            download_url
+        );
+    }
+}
+
+/// Replace every `{key}` of `template` by its value.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter().fold(template.to_string(), |acc, (key, value)| {
+        acc.replace(&format!("{{{key}}}"), value)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sections_override_default() {
+        let config: Config = toml::from_str(
+            r#"
+            [default]
+            author = "me"
+            zip_name = "{name}"
+
+            [glossary]
+            zip_name = "{dict_name}-{source}-{target}-translations"
+            "#,
+        )
+        .unwrap();
+
+        let glossary = config.section("glossary");
+        assert_eq!(glossary.author.as_deref(), Some("me"));
+        assert_eq!(
+            glossary.zip_name.as_deref(),
+            Some("{dict_name}-{source}-{target}-translations")
+        );
+
+        let main = config.section("main");
+        assert_eq!(main.zip_name.as_deref(), Some("{name}"));
+        assert_eq!(main.title, None);
+    }
+
+    #[test]
+    fn render_placeholders() {
+        let vars = [("source", "de"), ("target", "en")];
+        assert_eq!(render("dict/{source}/{target}", &vars), "dict/de/en");
+        assert_eq!(render("{unknown}", &vars), "{unknown}");
+    }
+}
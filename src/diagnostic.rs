@@ -1,9 +1,10 @@
 use std::fs;
+use std::path::Path;
 
 use anyhow::{Ok, Result};
 use indexmap::IndexMap;
 
-use crate::{Map, path::PathManager};
+use crate::{Map, lang::EditionLang, path::PathManager};
 
 type Key = String; // A tag
 type Word = String; // A word
@@ -18,6 +19,14 @@ pub struct Diagnostics {
     accepted_tags: Counter,
     /// Tags not found in bank
     rejected_tags: Counter,
+    /// Word level tags found in bank
+    accepted_term_tags: Counter,
+    /// Word level tags not found in bank
+    rejected_term_tags: Counter,
+    /// Sense raw tags not found in bank
+    raw_tags: Counter,
+    /// Labels that fall back to English, with the edition
+    missing_translations: Counter,
 }
 
 impl Diagnostics {
@@ -33,8 +42,29 @@ impl Diagnostics {
         Self::increment(&mut self.rejected_tags, tag, word);
     }
 
+    pub fn increment_accepted_term_tag(&mut self, tag: Key, word: Word) {
+        Self::increment(&mut self.accepted_term_tags, tag, word);
+    }
+
+    pub fn increment_rejected_term_tag(&mut self, tag: Key, word: Word) {
+        Self::increment(&mut self.rejected_term_tags, tag, word);
+    }
+
+    pub fn increment_raw_tag(&mut self, tag: Key, word: Word) {
+        Self::increment(&mut self.raw_tags, tag, word);
+    }
+
+    pub fn increment_missing_translation(&mut self, label: Key, edition: EditionLang) {
+        Self::increment(&mut self.missing_translations, label, edition.to_string());
+    }
+
     fn is_empty(&self) -> bool {
-        self.accepted_tags.is_empty() && self.rejected_tags.is_empty()
+        self.accepted_tags.is_empty()
+            && self.rejected_tags.is_empty()
+            && self.accepted_term_tags.is_empty()
+            && self.rejected_term_tags.is_empty()
+            && self.raw_tags.is_empty()
+            && self.missing_translations.is_empty()
     }
 
     pub fn write(&self, pm: &PathManager) -> Result<()> {
@@ -45,17 +75,49 @@ impl Diagnostics {
         let dir_diagnostics = pm.dir_diagnostics();
         fs::create_dir_all(&dir_diagnostics)?;
 
-        let accepted_sorted = convert_and_sort_indexmap(&self.accepted_tags);
-        let rejected_sorted = convert_and_sort_indexmap(&self.rejected_tags);
-        let json: Map<&'static str, _> =
-            Map::from_iter([("rejected", rejected_sorted), ("accepted", accepted_sorted)]);
-        let writer = fs::File::create(dir_diagnostics.join("tags.json"))?;
-        serde_json::to_writer_pretty(writer, &json)?;
+        if !self.accepted_tags.is_empty() || !self.rejected_tags.is_empty() {
+            write_counters(
+                &dir_diagnostics.join("tags.json"),
+                &self.accepted_tags,
+                &self.rejected_tags,
+            )?;
+        }
+
+        if !self.accepted_term_tags.is_empty() || !self.rejected_term_tags.is_empty() {
+            write_counters(
+                &dir_diagnostics.join("term_tags.json"),
+                &self.accepted_term_tags,
+                &self.rejected_term_tags,
+            )?;
+        }
+
+        if !self.raw_tags.is_empty() {
+            let writer = fs::File::create(dir_diagnostics.join("raw_tags.json"))?;
+            serde_json::to_writer_pretty(writer, &convert_and_sort_indexmap(&self.raw_tags))?;
+        }
+
+        if !self.missing_translations.is_empty() {
+            let writer = fs::File::create(dir_diagnostics.join("missing_translations.json"))?;
+            serde_json::to_writer_pretty(
+                writer,
+                &convert_and_sort_indexmap(&self.missing_translations),
+            )?;
+        }
 
         Ok(())
     }
 }
 
+fn write_counters(path: &Path, accepted: &Counter, rejected: &Counter) -> Result<()> {
+    let accepted_sorted = convert_and_sort_indexmap(accepted);
+    let rejected_sorted = convert_and_sort_indexmap(rejected);
+    let json: Map<&'static str, _> =
+        Map::from_iter([("rejected", rejected_sorted), ("accepted", accepted_sorted)]);
+    let writer = fs::File::create(path)?;
+    serde_json::to_writer_pretty(writer, &json)?;
+    Ok(())
+}
+
 // hacky: takes advantage of insertion order
 fn convert_and_sort_indexmap(map: &Counter) -> IndexMap<String, (usize, Word)> {
     // Display first word
@@ -69,7 +131,7 @@ fn convert_and_sort_indexmap(map: &Counter) -> IndexMap<String, (usize, Word)> {
         })
         .collect();
 
-    entries.sort_by(|a, b| b.1.0.cmp(&a.1.0));
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.1.0));
     let mut sorted = IndexMap::with_capacity(entries.len());
     for (key, value) in entries {
         sorted.insert(key, value);
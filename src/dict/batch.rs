@@ -0,0 +1,222 @@
+use anyhow::{Ok, Result};
+
+use std::cell::OnceCell;
+use std::path::PathBuf;
+
+use crate::Map;
+use crate::cli::Options;
+use crate::dict::{
+    Dictionary, LINES_PER_JOB, ProgressEvent, console_progress, find_or_download_jsonl,
+    finish_dict, open_jsonl, parse_lines, process_borrowed_word_entries, process_word_entries,
+    read_lines, rejected_json, take_first,
+};
+use crate::lang::{Edition, EditionLang};
+use crate::models::kaikki::WordEntry;
+use crate::path::PathManager;
+
+/// Type-erased dictionary, made as part of a batch. Cf. `make_dicts`.
+pub trait BatchDict: Send {
+    fn pm(&self) -> &PathManager;
+
+    fn options(&self) -> &Options;
+
+    /// Called before feeding the entries of a new jsonlines.
+    fn start_jsonl(&mut self);
+
+    /// Process entries, all of them accepted by the filters of this dictionary.
+    fn feed(&mut self, edition: EditionLang, word_entries: Vec<&WordEntry>);
+
+    /// Whether the `first` option was reached for the current jsonlines.
+    fn reached_first(&self) -> bool;
+
+    /// Postprocess and write the dictionary.
+    fn finish(self: Box<Self>) -> Result<()>;
+}
+
+/// A `Dictionary` together with its own options, paths and intermediate representation.
+pub struct BatchItem<D: Dictionary> {
+    dict: D,
+    options: Options,
+    pm: PathManager,
+    entries: D::I,
+    accepted_count: usize,
+    reached_first: bool,
+}
+
+impl<D: Dictionary + Send + 'static> BatchItem<D> {
+    pub fn boxed(dict: D, options: &Options, pm: PathManager) -> Box<dyn BatchDict> {
+        Box::new(Self {
+            dict,
+            options: options.clone(),
+            pm,
+            entries: D::I::default(),
+            accepted_count: 0,
+            reached_first: false,
+        })
+    }
+}
+
+impl<D: Dictionary + Send> BatchDict for BatchItem<D> {
+    fn pm(&self) -> &PathManager {
+        &self.pm
+    }
+
+    fn options(&self) -> &Options {
+        &self.options
+    }
+
+    fn start_jsonl(&mut self) {
+        self.accepted_count = 0;
+        self.reached_first = false;
+    }
+
+    fn feed(&mut self, edition: EditionLang, mut accepted: Vec<&WordEntry>) {
+        if self.reached_first {
+            return;
+        }
+
+        let (_, source, target) = self.pm.langs();
+
+        if D::MUTATES_ENTRIES {
+            // Clone, since other dictionaries may need the entry as it was.
+            let mut accepted: Vec<_> = accepted.into_iter().cloned().collect();
+            self.reached_first =
+                take_first(self.options.first, &mut self.accepted_count, &mut accepted);
+            process_word_entries(
+                &self.dict,
+                edition,
+                source,
+                target,
+                &self.options,
+                &mut accepted,
+                &mut self.entries,
+            );
+        } else {
+            self.reached_first =
+                take_first(self.options.first, &mut self.accepted_count, &mut accepted);
+            process_borrowed_word_entries(
+                &self.dict,
+                edition,
+                source,
+                target,
+                &self.options,
+                &mut accepted,
+                &mut self.entries,
+            );
+        }
+    }
+
+    fn reached_first(&self) -> bool {
+        self.reached_first
+    }
+
+    fn finish(self: Box<Self>) -> Result<()> {
+        let mut progress = console_progress(&self.options);
+        finish_dict(
+            &self.dict,
+            &self.options,
+            &self.pm,
+            self.entries,
+            progress.as_mut(),
+        )
+    }
+}
+
+/// Make every dictionary in `dicts`, reading each jsonlines only once.
+///
+/// Every word entry is dispatched to all the dictionaries that accept it, according to their own
+/// filters (usually by `lang_code`, cf. `prepare_command`).
+pub fn make_dicts(mut dicts: Vec<Box<dyn BatchDict>>, options: &Options) -> Result<()> {
+    // Group dictionaries by the jsonlines they read.
+    let mut groups: Map<(EditionLang, Vec<PathBuf>), Vec<usize>> = Map::default();
+    for (idx, dict) in dicts.iter().enumerate() {
+        dict.pm().setup_dirs()?;
+        for (edition, paths) in dict.pm().paths_jsonl() {
+            groups.entry((edition, paths)).or_default().push(idx);
+        }
+    }
+    // Dictionaries that read several editions read them in the order of `Edition::variants`, so
+    // that is the order of the groups, and every dictionary is fed as if it was made on its own.
+    let order = Edition::All.variants();
+    let rank = |edition: &EditionLang| order.iter().position(|edl| edl == edition);
+    groups.sort_by(|(a, _), _, (b, _), _| rank(a).cmp(&rank(b)));
+
+    let mut progress = console_progress(options);
+    let jobs = options.worker_count();
+    let batch_size = jobs * LINES_PER_JOB;
+    let mut lines = Vec::new();
+
+    for ((edition, paths), idxs) in groups {
+        // The source is only relevant for the English edition, whose paths depend on it.
+        let (_, source, _) = dicts[idxs[0]].pm().langs();
+        let path_jsonl = find_or_download_jsonl(edition, source, &paths, dicts[idxs[0]].options())?;
+        tracing::debug!(
+            "path_jsonl: {} ({} dicts)",
+            path_jsonl.display(),
+            idxs.len()
+        );
+
+        let mut reader = open_jsonl(&path_jsonl)?;
+
+        let group_options: Vec<_> = idxs
+            .iter()
+            .map(|&idx| dicts[idx].options().clone())
+            .collect();
+        let linkages: Vec<_> = group_options
+            .iter()
+            .flat_map(Options::kept_linkages)
+            .collect();
+        for &idx in &idxs {
+            dicts[idx].start_jsonl();
+        }
+
+        let mut line_count = 0;
+
+        loop {
+            let n_read = read_lines(&mut reader, &mut lines, batch_size)?;
+            if n_read == 0 {
+                break; // EOF
+            }
+            line_count += n_read;
+
+            // Only keep what at least one dictionary needs, and which dictionaries need it. The
+            // entry is serialized at most once for the `--where` expressions of every dictionary.
+            let word_entries = parse_lines(&mut lines[..n_read], jobs, &linkages, |entry| {
+                let json = OnceCell::new();
+                let accepted: Vec<_> = group_options
+                    .iter()
+                    .map(|opts| !rejected_json(entry, &json, opts))
+                    .collect();
+                accepted.contains(&true).then_some(accepted)
+            })?;
+
+            for (pos, &idx) in idxs.iter().enumerate() {
+                let accepted = word_entries
+                    .iter()
+                    .filter(|(_, accepted)| accepted[pos])
+                    .map(|(entry, _)| entry)
+                    .collect();
+                dicts[idx].feed(edition, accepted);
+            }
+
+            progress.report(ProgressEvent::Lines {
+                processed: line_count,
+            });
+
+            if n_read < batch_size || idxs.iter().all(|&idx| dicts[idx].reached_first()) {
+                break;
+            }
+        }
+
+        progress.report(ProgressEvent::LinesDone {
+            processed: line_count,
+            accepted: None,
+        });
+    }
+
+    for dict in dicts {
+        dict.finish()?;
+    }
+
+    Ok(())
+}
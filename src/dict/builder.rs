@@ -0,0 +1,380 @@
+//! Library API to make a dictionary from any reader into any writer, without the CLI.
+//!
+//! ```no_run
+//! use std::fs::File;
+//! use std::io::Cursor;
+//!
+//! use kty::dict::{DMain, DictBuilder, ProgressEvent};
+//! use kty::lang::{EditionLang, Lang};
+//!
+//! let input = File::open("de-en-extract.jsonl")?;
+//! let zip = DictBuilder::new(DMain)
+//!     .edition(EditionLang::En)
+//!     .source(Lang::De)
+//!     .target(Lang::En)
+//!     .progress(|event: ProgressEvent| eprintln!("{event:?}"))
+//!     .build(input, Cursor::new(Vec::new()))?
+//!     .into_inner();
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use anyhow::{Context, Ok, Result};
+
+use std::io::{BufReader, Read, Seek, Write};
+use std::sync::Arc;
+
+use crate::cli::{FilterKey, GlossaryExtendedLangs, Langs, Options, SimpleArgs};
+use crate::config::Config;
+use crate::diagnostic::Diagnostics;
+use crate::dict::{
+    Dictionary, Intermediate, NoProgress, Progress, ProgressEvent, convert_to_yomitan, read_jsonl,
+    write_zip,
+};
+use crate::filter::Expr;
+use crate::freq::FreqList;
+use crate::lang::{Edition, EditionLang, Lang};
+use crate::models::kaikki::{ExamplePreference, LinkageKind};
+use crate::path::{DictionaryType, PathManager};
+
+/// Builder for a dictionary of type `D`. Cf. the module documentation.
+///
+/// Unlike the CLI, it never touches the disk: there is no download, no temporary files and no
+/// diagnostics.
+pub struct DictBuilder<'a, D: Dictionary> {
+    dict: D,
+    dict_name: String,
+    edition: Option<EditionLang>,
+    source: Option<Lang>,
+    target: Option<Lang>,
+    options: Options,
+    progress: Box<dyn Progress + 'a>,
+}
+
+impl<'a, D: Dictionary> DictBuilder<'a, D> {
+    pub fn new(dict: D) -> Self {
+        Self {
+            dict,
+            dict_name: "kty".to_string(),
+            edition: None,
+            source: None,
+            target: None,
+            options: Options {
+                first: -1,
+                ..Default::default()
+            },
+            progress: Box::new(NoProgress),
+        }
+    }
+
+    /// Edition of the jsonlines that will be read.
+    #[must_use]
+    pub const fn edition(mut self, edition: EditionLang) -> Self {
+        self.edition = Some(edition);
+        self
+    }
+
+    #[must_use]
+    pub const fn source(mut self, source: Lang) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    #[must_use]
+    pub const fn target(mut self, target: Lang) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Prefix of the dictionary title. Defaults to `kty`.
+    #[must_use]
+    pub fn dict_name(mut self, dict_name: impl Into<String>) -> Self {
+        self.dict_name = dict_name.into();
+        self
+    }
+
+    /// Only keep entries matching this filter. Can be called multiple times.
+    #[must_use]
+    pub fn filter(mut self, key: FilterKey, value: impl Into<String>) -> Self {
+        self.options.filter.push((key, value.into()));
+        self
+    }
+
+    /// Only keep entries not matching this filter. Can be called multiple times.
+    #[must_use]
+    pub fn reject(mut self, key: FilterKey, value: impl Into<String>) -> Self {
+        self.options.reject.push((key, value.into()));
+        self
+    }
+
+    /// Only keep entries matching this `--where` expression. Can be called multiple times.
+    #[must_use]
+    pub fn where_expr(mut self, expr: Expr) -> Self {
+        self.options.where_exprs.push(expr);
+        self
+    }
+
+    /// Only keep the first `n` filtered lines.
+    #[must_use]
+    pub fn first(mut self, n: usize) -> Self {
+        self.options.first = i32::try_from(n).unwrap_or(i32::MAX);
+        self
+    }
+
+    /// Number of worker threads. 0 (the default) uses all available cores.
+    #[must_use]
+    pub const fn jobs(mut self, jobs: usize) -> Self {
+        self.options.jobs = jobs;
+        self
+    }
+
+    /// Write jsons with whitespace.
+    #[must_use]
+    pub const fn pretty(mut self, pretty: bool) -> Self {
+        self.options.pretty = pretty;
+        self
+    }
+
+    /// Include experimental features.
+    #[must_use]
+    pub const fn experimental(mut self, experimental: bool) -> Self {
+        self.options.experimental = experimental;
+        self
+    }
+
+    /// Related words shown after the glosses. None by default.
+    #[must_use]
+    pub fn linkages(mut self, kinds: impl IntoIterator<Item = LinkageKind>) -> Self {
+        self.options.linkages = kinds.into_iter().collect();
+        self
+    }
+
+    /// Maximum number of examples per sense. All are kept by default.
+    #[must_use]
+    pub const fn max_examples(mut self, n: usize) -> Self {
+        self.options.max_examples = Some(n);
+        self
+    }
+
+    /// Maximum length of an example, in characters. 120 by default.
+    #[must_use]
+    pub const fn max_example_length(mut self, n: usize) -> Self {
+        self.options.max_example_length = Some(n);
+        self
+    }
+
+    /// Which examples come first. Translated ones by default.
+    #[must_use]
+    pub const fn prefer_examples(mut self, preference: ExamplePreference) -> Self {
+        self.options.prefer_examples = preference;
+        self
+    }
+
+    /// Frequency list used to score entries. Required for the frequency dictionary.
+    #[must_use]
+    pub fn freq_list(mut self, freq_list: impl Into<Arc<FreqList>>) -> Self {
+        self.options.freq_list = Some(freq_list.into());
+        self
+    }
+
+    /// Index templates. Output paths are ignored, since the caller provides the writer.
+    #[must_use]
+    pub fn config(mut self, config: Config) -> Self {
+        self.options.config = Some(config);
+        self
+    }
+
+    /// Where to report progress. Nothing is reported by default.
+    #[must_use]
+    pub fn progress(mut self, progress: impl Progress + 'a) -> Self {
+        self.progress = Box::new(progress);
+        self
+    }
+
+    /// Read the jsonlines from `input`, and write the zipped dictionary to `output`.
+    ///
+    /// Return `output`, for instance to recover the buffer of a `Cursor`.
+    pub fn build<R: Read, W: Write + Seek>(self, input: R, output: W) -> Result<W> {
+        let Self {
+            dict,
+            dict_name,
+            edition,
+            source,
+            target,
+            mut options,
+            mut progress,
+        } = self;
+        let edition = edition.context("DictBuilder: missing edition")?;
+        let source = source.context("DictBuilder: missing source")?;
+        let target = target.context("DictBuilder: missing target")?;
+
+        match D::DICT_TYPE {
+            DictionaryType::Freq => anyhow::ensure!(
+                options.freq_list.is_some(),
+                "a frequency dictionary requires a frequency list."
+            ),
+            // Same filter as the CLI (cf. `prepare_command`)
+            DictionaryType::GlossaryExtended => (),
+            _ => options
+                .filter
+                .push((FilterKey::LangCode, source.to_string())),
+        }
+
+        let args = BuilderArgs {
+            dict_name,
+            langs: GlossaryExtendedLangs {
+                edition: Edition::EditionLang(edition),
+                source,
+                target,
+            },
+            options,
+        };
+        let pm = PathManager::new(D::DICT_TYPE, &args);
+        let options = &args.options;
+
+        let mut reader = BufReader::new(input);
+        let mut entries = D::I::default();
+        read_jsonl(
+            &dict,
+            edition,
+            &pm,
+            options,
+            &mut reader,
+            &mut Vec::new(),
+            &mut entries,
+            progress.as_mut(),
+        )?;
+
+        progress.report(ProgressEvent::Found {
+            message: &dict.found_ir_message(&entries),
+        });
+
+        if !entries.is_empty() {
+            dict.postprocess(&mut entries);
+        }
+
+        let mut diagnostics = Diagnostics::default();
+        let labelled_entries = convert_to_yomitan(&dict, options, &pm, &mut diagnostics, entries);
+        let output = write_zip(
+            output,
+            options,
+            &pm,
+            &labelled_entries,
+            None,
+            progress.as_mut(),
+        )?;
+
+        progress.report(ProgressEvent::Written {
+            what: "yomitan dict",
+            path: None,
+        });
+
+        Ok(output)
+    }
+}
+
+/// What the `PathManager` needs to know about the dictionary.
+struct BuilderArgs {
+    dict_name: String,
+    langs: GlossaryExtendedLangs,
+    options: Options,
+}
+
+impl SimpleArgs for BuilderArgs {
+    fn dict_name(&self) -> &str {
+        &self.dict_name
+    }
+    fn langs(&self) -> &impl Langs {
+        &self.langs
+    }
+    fn options(&self) -> &Options {
+        &self.options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::File;
+    use std::io::Cursor;
+    use std::path::Path;
+
+    use zip::ZipArchive;
+
+    use crate::dict::{DGlossary, DMain};
+
+    fn fixture(name: &str) -> File {
+        File::open(
+            Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests/kaikki")
+                .join(name),
+        )
+        .unwrap()
+    }
+
+    /// The first term bank of the de-en main dictionary made by `builder`.
+    fn term_bank(builder: DictBuilder<DMain>) -> String {
+        let output = builder
+            .edition(EditionLang::En)
+            .source(Lang::De)
+            .target(Lang::En)
+            .build(fixture("de-en-extract.jsonl"), Cursor::new(Vec::new()))
+            .unwrap();
+        let mut zip = ZipArchive::new(output).unwrap();
+        std::io::read_to_string(zip.by_name("term_bank_1.json").unwrap()).unwrap()
+    }
+
+    #[test]
+    fn build_to_memory() {
+        let mut events = Vec::new();
+        let output = DictBuilder::new(DMain)
+            .edition(EditionLang::En)
+            .source(Lang::De)
+            .target(Lang::En)
+            .progress(|event: ProgressEvent| events.push(format!("{event:?}")))
+            .build(fixture("de-en-extract.jsonl"), Cursor::new(Vec::new()))
+            .unwrap();
+
+        let zip = ZipArchive::new(output).unwrap();
+        let names: Vec<_> = zip.file_names().collect();
+        assert!(names.contains(&"index.json"));
+        assert!(names.contains(&"term_bank_1.json"));
+
+        assert!(events.iter().any(|event| event.starts_with("LinesDone")));
+        assert!(events.last().unwrap().starts_with("Written"));
+    }
+
+    #[test]
+    fn build_with_linkages() {
+        let with_linkages = |kinds: &[LinkageKind]| {
+            term_bank(DictBuilder::new(DMain).linkages(kinds.iter().copied()))
+        };
+
+        assert!(!with_linkages(&[]).contains("details-entry-Derived-terms"));
+        let with_derived = with_linkages(&[LinkageKind::Derived]);
+        assert!(with_derived.contains("details-entry-Derived-terms"));
+        assert!(!with_derived.contains("details-entry-Synonyms"));
+    }
+
+    #[test]
+    fn build_with_example_policy() {
+        assert!(term_bank(DictBuilder::new(DMain)).contains("details-entry-examples"));
+        assert!(
+            !term_bank(DictBuilder::new(DMain).max_examples(0)).contains("details-entry-examples")
+        );
+        assert!(
+            !term_bank(DictBuilder::new(DMain).max_example_length(0))
+                .contains("details-entry-examples")
+        );
+    }
+
+    #[test]
+    fn build_requires_langs() {
+        let err = DictBuilder::new(DGlossary)
+            .source(Lang::De)
+            .target(Lang::En)
+            .build(fixture("de-en-extract.jsonl"), Cursor::new(Vec::new()))
+            .unwrap_err();
+        assert!(err.to_string().contains("missing edition"));
+    }
+}
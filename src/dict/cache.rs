@@ -0,0 +1,309 @@
+//! Cache of `--incremental` builds.
+//!
+//! Every line of the jsonlines contributes independently to the intermediate representation
+//! (cf. `Intermediate::merge`), so we store the contribution of each line, keyed by a hash of its
+//! content. The next run only parses and processes the lines that are not in the cache, that is,
+//! the lines that were added or changed since. Removed lines are simply never looked up.
+//!
+//! That is all that is skipped: every line is still read and hashed, every cached contribution is
+//! deserialized and merged again from scratch, and the whole cache is rewritten.
+//!
+//! The cache is a text file with one line per jsonlines line: the hash, a tab, and the serialized
+//! `Intermediate` (empty if the line was rejected by the filters). The first line is a
+//! fingerprint of everything else that could change the result, like the kty version, the
+//! options read by `Dictionary::preprocess` or the rule files. If it does not match, the cache is
+//! ignored. Both are FNV-1a hashes, which do not change with the toolchain.
+
+use anyhow::{Context, Ok, Result};
+
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use crate::Map;
+use crate::cli::Options;
+use crate::dict::{
+    Dictionary, Intermediate, LINES_PER_JOB, Progress, ProgressEvent, par_map_chunks, read_lines,
+    rejected, rules_contents, take_first,
+};
+use crate::lang::{EditionLang, Lang};
+use crate::models::kaikki::{LinkageKind, WordEntry};
+use crate::path::PathManager;
+use crate::utils::Fnv1a;
+
+const HEADER_PREFIX: &str = "kty-cache ";
+
+/// Keys are persisted, so they must not depend on the toolchain, cf. `Fnv1a`.
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = Fnv1a::default();
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Hash of everything, besides the line itself, that changes what a line contributes.
+fn fingerprint<D: Dictionary>(
+    edition: EditionLang,
+    source: Lang,
+    target: Lang,
+    options: &Options,
+) -> u64 {
+    let key = format!(
+        "{} {:?} {edition:?} {source:?} {target:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?} {:?}",
+        env!("CARGO_PKG_VERSION"),
+        D::DICT_TYPE,
+        options.experimental,
+        options.max_examples,
+        options.max_example_length,
+        options.prefer_examples,
+        options.linkages,
+        options.filter,
+        options.reject,
+        options.where_exprs,
+    );
+    let mut hasher = Fnv1a::default();
+    hasher.write(key.as_bytes());
+    for content in rules_contents() {
+        hasher.write(&[0]);
+        hasher.write(content.as_bytes());
+    }
+    hasher.finish()
+}
+
+/// The contributions of the previous run, and those of the current one, that replace them.
+struct Cache {
+    path: PathBuf,
+    path_tmp: PathBuf,
+    /// Serialized `Intermediate` of each line of the previous run, by hash.
+    old: Map<u64, String>,
+    new: BufWriter<File>,
+}
+
+impl Cache {
+    fn open(path: PathBuf, fingerprint: u64) -> Result<Self> {
+        let header = format!("{HEADER_PREFIX}{fingerprint:016x}");
+
+        let mut old = Map::default();
+        if let Result::Ok(file) = File::open(&path) {
+            let mut lines = BufReader::new(file).lines();
+            if lines.next().transpose()?.as_deref() == Some(header.as_str()) {
+                for line in lines {
+                    let line = line?;
+                    let (hash, ir) = line
+                        .split_once('\t')
+                        .with_context(|| format!("corrupted cache: {}", path.display()))?;
+                    let hash = u64::from_str_radix(hash, 16)
+                        .with_context(|| format!("corrupted cache: {}", path.display()))?;
+                    old.insert(hash, ir.to_string());
+                }
+            } else {
+                tracing::info!("Outdated cache, processing every line: {}", path.display());
+            }
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let path_tmp = path.with_extension("tsv.tmp");
+        let mut new = BufWriter::new(File::create(&path_tmp)?);
+        writeln!(new, "{header}")?;
+
+        Ok(Self {
+            path,
+            path_tmp,
+            old,
+            new,
+        })
+    }
+
+    /// Take the contribution of a line from the previous run, if it had this line.
+    ///
+    /// Taking it means that a repeated line is processed again, which is rare enough.
+    fn take(&mut self, hash: u64) -> Option<String> {
+        self.old.swap_remove(&hash)
+    }
+
+    fn write(&mut self, hash: u64, ir: &str) -> Result<()> {
+        writeln!(self.new, "{hash:016x}\t{ir}")?;
+        Ok(())
+    }
+
+    /// Replace the cache of the previous run, only once the current one is complete.
+    fn finish(self) -> Result<PathBuf> {
+        self.new.into_inner()?.sync_all()?;
+        fs::rename(&self.path_tmp, &self.path)?;
+        Ok(self.path)
+    }
+}
+
+/// What a line contributes: its intermediate representation (None if rejected), and the same
+/// serialized for the cache.
+type Contribution<I> = (Option<I>, String);
+
+fn process_line<D: Dictionary>(
+    dict: &D,
+    edition: EditionLang,
+    source: Lang,
+    target: Lang,
+    options: &Options,
+    linkages: &[LinkageKind],
+    line: &[u8],
+) -> Result<Contribution<D::I>> {
+    let mut word_entry =
+        WordEntry::from_json(line, linkages).with_context(|| "Error decoding JSON @ make_dict")?;
+    if rejected(&word_entry, options) {
+        return Ok((None, String::new()));
+    }
+
+    let mut irs = D::I::default();
+    dict.preprocess(edition, source, target, &mut word_entry, options, &mut irs);
+    dict.process(edition, source, target, &word_entry, &mut irs);
+    let cache = irs.to_cache()?;
+    Ok((Some(irs), cache))
+}
+
+fn load_line<I: Intermediate>(cache: String) -> Result<Contribution<I>> {
+    if cache.is_empty() {
+        return Ok((None, cache));
+    }
+    Ok((Some(I::from_cache(&cache)?), cache))
+}
+
+/// Same as `read_jsonl`, but taking the contribution of the lines that did not change from the
+/// cache of the previous `--incremental` run.
+#[allow(clippy::too_many_arguments)]
+pub fn read_jsonl_incremental<D: Dictionary>(
+    dict: &D,
+    edition: EditionLang,
+    pm: &PathManager,
+    options: &Options,
+    reader: &mut impl BufRead,
+    lines: &mut Vec<Vec<u8>>,
+    entries: &mut D::I,
+    progress: &mut dyn Progress,
+) -> Result<()> {
+    let (_, source_pm, target_pm) = pm.langs();
+    let jobs = options.worker_count();
+    let batch_size = jobs * LINES_PER_JOB;
+
+    let linkages = options.kept_linkages();
+    let fingerprint = fingerprint::<D>(edition, source_pm, target_pm, options);
+    let mut cache = Cache::open(pm.path_cache(edition), fingerprint)?;
+
+    let mut line_count = 0;
+    let mut accepted_count = 0;
+    let mut reused = 0;
+
+    loop {
+        let n_read = read_lines(reader, lines, batch_size)?;
+        if n_read == 0 {
+            break; // EOF
+        }
+        line_count += n_read;
+
+        let mut work: Vec<_> = lines[..n_read]
+            .iter()
+            .map(|line| {
+                let hash = hash(line);
+                (hash, cache.take(hash), line.as_slice())
+            })
+            .collect();
+        reused += work
+            .iter()
+            .filter(|(_, cached, _)| cached.is_some())
+            .count();
+
+        // Deserializing the cached lines is not free either, so they are also loaded in parallel
+        let contributions = par_map_chunks(&mut work, jobs, |chunk| {
+            chunk
+                .iter_mut()
+                .map(|(_, cached, line)| match cached.take() {
+                    Some(cached) => load_line(cached),
+                    None => process_line(
+                        dict, edition, source_pm, target_pm, options, &linkages, line,
+                    ),
+                })
+                .collect::<Vec<_>>()
+        });
+
+        let mut irs = Vec::new();
+        for ((hash, _, _), contribution) in work.iter().zip(contributions.into_iter().flatten()) {
+            let (ir, serialized) = contribution?;
+            cache.write(*hash, &serialized)?;
+            irs.extend(ir);
+        }
+
+        let reached_first = take_first(options.first, &mut accepted_count, &mut irs);
+
+        for ir in irs {
+            entries.merge(ir);
+        }
+
+        progress.report(ProgressEvent::Lines {
+            processed: line_count,
+        });
+
+        if reached_first || n_read < batch_size {
+            break;
+        }
+    }
+
+    let path = cache.finish()?;
+
+    progress.report(ProgressEvent::LinesDone {
+        processed: line_count,
+        accepted: Some(accepted_count),
+    });
+    progress.report(ProgressEvent::Cached {
+        reused,
+        processed: line_count - reused,
+        path: &path,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stable_hash() {
+        // Reference values of 64-bit FNV-1a
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn cache_round_trip() {
+        let path = std::env::temp_dir().join(format!("kty-cache-{}.tsv", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut cache = Cache::open(path.clone(), 1).unwrap();
+        assert_eq!(cache.take(42), None);
+        cache.write(42, r#"[["word", "rejected\tnot"]]"#).unwrap();
+        cache.write(43, "").unwrap();
+        cache.finish().unwrap();
+
+        let mut cache = Cache::open(path.clone(), 1).unwrap();
+        assert_eq!(
+            cache.take(42).as_deref(),
+            Some(r#"[["word", "rejected\tnot"]]"#)
+        );
+        assert_eq!(cache.take(43).as_deref(), Some(""));
+        assert_eq!(cache.take(42), None);
+        cache.finish().unwrap();
+
+        let mut cache = Cache::open(path.clone(), 1).unwrap();
+        cache.write(44, "[]").unwrap();
+        cache.finish().unwrap();
+
+        // A different fingerprint invalidates the cache
+        let mut cache = Cache::open(path.clone(), 2).unwrap();
+        assert_eq!(cache.take(44), None);
+        cache.finish().unwrap();
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,12 +1,16 @@
+use serde_json::Value;
+
+use crate::Map;
 use crate::lang::Lang;
+use crate::path::{DictionaryType, PathManager};
 
 const BASE_URL: &str = "https://huggingface.co/datasets/daxida/test-dataset/resolve/main";
 
 /// The url to download this dictionary.
 ///
 /// See: docs/javascripts/download.js (keep in sync)
-fn download_url(dict_name_expanded: &str, source: Lang, target: Lang) -> String {
-    format!("{BASE_URL}/dict/{target}/{source}/{dict_name_expanded}.zip?download=true")
+fn download_url(zip_name: &str, source: Lang, target: Lang) -> String {
+    format!("{BASE_URL}/dict/{target}/{source}/{zip_name}.zip?download=true")
 }
 
 /// The url of the cloned index of this dictionary.
@@ -34,34 +38,104 @@ fn index_url(dict_name_expanded: &str) -> String {
 /// indexUrl points to a separate copy of the index in the download repository.
 /// downloadUrl points to the download link in the download repository.
 ///
+/// frequencyMode is only set for frequency dictionaries, whose values are ranks.
+///
+/// Most fields can be changed with the config file templates (`--config`).
+///
 /// <https://github.com/yomidevs/yomitan/blob/master/ext/data/schemas/dictionary-index-schema.json>
-pub fn get_index(dict_name_expanded: &str, source: Lang, target: Lang) -> String {
+pub fn get_index(pm: &PathManager) -> String {
+    let (_, source, target) = pm.langs();
+    let dict_name_expanded = pm.dict_name_expanded();
+    let config = pm.config();
+    let render_or = |template: &Option<String>, default: String| -> Value {
+        template
+            .as_ref()
+            .map_or(default, |template| pm.render(template))
+            .into()
+    };
+
     let current_date = chrono::Utc::now().format("%Y.%m.%d"); // needs to be dot separated
-    let index_url = index_url(dict_name_expanded);
-    let download_url = download_url(dict_name_expanded, source, target);
-    format!(
-        r#"{{
-  "title": "{dict_name_expanded}",
-  "format": 3,
-  "revision": "{current_date}",
-  "sequenced": true,
-  "author": "kty contributors",
-  "url": "https://github.com/daxida/kty",
-  "description": "Dictionaries for various language pairs generated from Wiktionary data, via Kaikki and kty.",
-  "attribution": "https://kaikki.org/",
-  "sourceLanguage": "{source}",
-  "targetLanguage": "{target}",
-  "isUpdatable": true,
-  "indexUrl": "{index_url}",
-  "downloadUrl": "{download_url}"
-}}"#
-    )
+
+    let mut index: Map<&str, Value> = Map::default();
+    index.insert(
+        "title",
+        render_or(&config.title, dict_name_expanded.clone()),
+    );
+    index.insert("format", 3.into());
+    index.insert("revision", current_date.to_string().into());
+    index.insert("sequenced", true.into());
+    if matches!(pm.dict_ty(), DictionaryType::Freq) {
+        index.insert("frequencyMode", "rank-based".into());
+    }
+    index.insert(
+        "author",
+        render_or(&config.author, "kty contributors".to_string()),
+    );
+    index.insert("url", "https://github.com/daxida/kty".into());
+    index.insert(
+        "description",
+        render_or(
+            &config.description,
+            "Dictionaries for various language pairs generated from Wiktionary data, via Kaikki and kty.".to_string(),
+        ),
+    );
+    index.insert(
+        "attribution",
+        render_or(&config.attribution, "https://kaikki.org/".to_string()),
+    );
+    index.insert("sourceLanguage", source.to_string().into());
+    index.insert("targetLanguage", target.to_string().into());
+    index.insert("isUpdatable", true.into());
+    index.insert(
+        "indexUrl",
+        render_or(&config.index_url, index_url(&dict_name_expanded)),
+    );
+    index.insert(
+        "downloadUrl",
+        render_or(
+            &config.download_url,
+            download_url(&pm.zip_name(), source, target),
+        ),
+    );
+
+    serde_json::to_string_pretty(&index).expect("the index is valid json")
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::cli::{MainArgs, MainLangs};
+    use crate::config::Config;
+    use crate::lang::EditionLang;
+
+    fn index_of(dict_ty: DictionaryType, config: Option<Config>) -> Value {
+        let mut args = MainArgs {
+            langs: MainLangs {
+                edition: EditionLang::En,
+                source: Lang::Ja,
+                target: EditionLang::En,
+            },
+            dict_name: "kty".to_string(),
+            ..Default::default()
+        };
+        args.options.config = config;
+        let pm = PathManager::new(dict_ty, &args);
+        serde_json::from_str(&get_index(&pm)).unwrap()
+    }
+
+    #[test]
+    fn frequency_mode() {
+        assert_eq!(
+            index_of(DictionaryType::Freq, None)["frequencyMode"],
+            "rank-based"
+        );
+        assert_eq!(
+            index_of(DictionaryType::Main, None).get("frequencyMode"),
+            None
+        );
+    }
+
     #[test]
     fn urls() {
         let dict_name = "kty-afb-en-ipa";
@@ -76,4 +150,23 @@ mod tests {
             "https://huggingface.co/datasets/daxida/test-dataset/resolve/main/index/kty-afb-en-ipa-index?download=true"
         );
     }
+
+    #[test]
+    fn download_url_follows_zip_name() {
+        let config: Config = toml::from_str(
+            r#"
+            [main]
+            zip_name = "{dict_name}-{source}-{target}-{type}"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(
+            index_of(DictionaryType::Main, Some(config))["downloadUrl"],
+            "https://huggingface.co/datasets/daxida/test-dataset/resolve/main/dict/en/ja/kty-ja-en-main.zip?download=true"
+        );
+        assert_eq!(
+            index_of(DictionaryType::Main, None)["downloadUrl"],
+            "https://huggingface.co/datasets/daxida/test-dataset/resolve/main/dict/en/ja/kty-ja-en.zip?download=true"
+        );
+    }
 }
@@ -1,20 +1,519 @@
+//! Localization of the strings that the dictionaries show, besides the wiktionary content.
+//!
+//! Every label is written in the language of the edition, since that is the language the user
+//! reads the glosses in. Missing translations fall back to English, and are reported in the
+//! diagnostics (cf. `Diagnostics::increment_missing_translation`).
+
 use crate::lang::EditionLang;
+use crate::models::kaikki::LinkageKind;
+
+/// Every user-visible string of the dictionaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    /// Summary of a single example. `{n}` is replaced by the count
+    Example,
+    /// Summary of multiple examples. `{n}` is replaced by the count
+    Examples,
+    Grammar,
+    Etymology,
+    UsageNotes,
+    Synonyms,
+    Antonyms,
+    Hypernyms,
+    DerivedTerms,
+    RelatedTerms,
+    CoordinateTerms,
+    /// Backlink to the wiktionary page
+    Wiktionary,
+    /// Inflection of a form without a better description. `{word}` is replaced by the form
+    RedirectedFrom,
+    /// Separator between alternative forms
+    Or,
+}
+
+impl Label {
+    pub const ALL: [Self; 14] = [
+        Self::Example,
+        Self::Examples,
+        Self::Grammar,
+        Self::Etymology,
+        Self::UsageNotes,
+        Self::Synonyms,
+        Self::Antonyms,
+        Self::Hypernyms,
+        Self::DerivedTerms,
+        Self::RelatedTerms,
+        Self::CoordinateTerms,
+        Self::Wiktionary,
+        Self::RedirectedFrom,
+        Self::Or,
+    ];
+
+    /// The English string, also used for html ids.
+    pub fn english(self) -> &'static str {
+        EN.get(self).expect("every label has an English string")
+    }
+}
+
+impl From<LinkageKind> for Label {
+    fn from(kind: LinkageKind) -> Self {
+        match kind {
+            LinkageKind::Synonyms => Self::Synonyms,
+            LinkageKind::Antonyms => Self::Antonyms,
+            LinkageKind::Hypernyms => Self::Hypernyms,
+            LinkageKind::Derived => Self::DerivedTerms,
+            LinkageKind::Related => Self::RelatedTerms,
+            LinkageKind::CoordinateTerms => Self::CoordinateTerms,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Locale {
+    example: Option<&'static str>,
+    examples: Option<&'static str>,
+    grammar: Option<&'static str>,
+    etymology: Option<&'static str>,
+    usage_notes: Option<&'static str>,
+    synonyms: Option<&'static str>,
+    antonyms: Option<&'static str>,
+    hypernyms: Option<&'static str>,
+    derived_terms: Option<&'static str>,
+    related_terms: Option<&'static str>,
+    coordinate_terms: Option<&'static str>,
+    wiktionary: Option<&'static str>,
+    redirected_from: Option<&'static str>,
+    or: Option<&'static str>,
+}
+
+impl Locale {
+    const fn get(&self, label: Label) -> Option<&'static str> {
+        match label {
+            Label::Example => self.example,
+            Label::Examples => self.examples,
+            Label::Grammar => self.grammar,
+            Label::Etymology => self.etymology,
+            Label::UsageNotes => self.usage_notes,
+            Label::Synonyms => self.synonyms,
+            Label::Antonyms => self.antonyms,
+            Label::Hypernyms => self.hypernyms,
+            Label::DerivedTerms => self.derived_terms,
+            Label::RelatedTerms => self.related_terms,
+            Label::CoordinateTerms => self.coordinate_terms,
+            Label::Wiktionary => self.wiktionary,
+            Label::RedirectedFrom => self.redirected_from,
+            Label::Or => self.or,
+        }
+    }
+}
+
+const MISSING: Locale = Locale {
+    example: None,
+    examples: None,
+    grammar: None,
+    etymology: None,
+    usage_notes: None,
+    synonyms: None,
+    antonyms: None,
+    hypernyms: None,
+    derived_terms: None,
+    related_terms: None,
+    coordinate_terms: None,
+    wiktionary: None,
+    redirected_from: None,
+    or: None,
+};
+
+const EN: Locale = Locale {
+    example: Some("1 example"),
+    examples: Some("{n} examples"),
+    grammar: Some("Grammar"),
+    etymology: Some("Etymology"),
+    usage_notes: Some("Usage notes"),
+    synonyms: Some("Synonyms"),
+    antonyms: Some("Antonyms"),
+    hypernyms: Some("Hypernyms"),
+    derived_terms: Some("Derived terms"),
+    related_terms: Some("Related terms"),
+    coordinate_terms: Some("Coordinate terms"),
+    wiktionary: Some("Wiktionary"),
+    redirected_from: Some("redirected from {word}"),
+    or: Some("or"),
+};
+
+const CS: Locale = Locale {
+    example: Some("1 příklad"),
+    examples: Some("{n} příklady"),
+    grammar: Some("Mluvnice"),
+    etymology: Some("Etymologie"),
+    usage_notes: Some("Poznámky k užití"),
+    synonyms: Some("Synonyma"),
+    antonyms: Some("Antonyma"),
+    hypernyms: Some("Hyperonyma"),
+    derived_terms: Some("Odvozená slova"),
+    related_terms: Some("Související slova"),
+    coordinate_terms: Some("Kohyponyma"),
+    wiktionary: Some("Wikislovník"),
+    redirected_from: Some("přesměrováno z {word}"),
+    or: Some("nebo"),
+};
+
+const DE: Locale = Locale {
+    example: Some("1 Beispiel"),
+    examples: Some("{n} Beispiele"),
+    grammar: Some("Grammatik"),
+    etymology: Some("Herkunft"),
+    usage_notes: Some("Verwendungshinweise"),
+    synonyms: Some("Synonyme"),
+    antonyms: Some("Gegenwörter"),
+    hypernyms: Some("Oberbegriffe"),
+    derived_terms: Some("Wortbildungen"),
+    related_terms: Some("Verwandte Begriffe"),
+    coordinate_terms: Some("Kohyponyme"),
+    wiktionary: Some("Wiktionary"),
+    redirected_from: Some("weitergeleitet von {word}"),
+    or: Some("oder"),
+};
+
+const EL: Locale = Locale {
+    example: Some("1 παράδειγμα"),
+    examples: Some("{n} παραδείγματα"),
+    grammar: Some("Γραμματική"),
+    etymology: Some("Ετυμολογία"),
+    usage_notes: Some("Σημειώσεις χρήσης"),
+    synonyms: Some("Συνώνυμα"),
+    antonyms: Some("Αντώνυμα"),
+    hypernyms: Some("Υπερώνυμα"),
+    derived_terms: Some("Παράγωγα"),
+    related_terms: Some("Συγγενικά"),
+    wiktionary: Some("Βικιλεξικό"),
+    redirected_from: Some("ανακατεύθυνση από {word}"),
+    or: Some("ή"),
+    ..MISSING
+};
+
+const ES: Locale = Locale {
+    example: Some("1 ejemplo"),
+    examples: Some("{n} ejemplos"),
+    grammar: Some("Gramática"),
+    etymology: Some("Etimología"),
+    usage_notes: Some("Notas de uso"),
+    synonyms: Some("Sinónimos"),
+    antonyms: Some("Antónimos"),
+    hypernyms: Some("Hiperónimos"),
+    derived_terms: Some("Términos derivados"),
+    related_terms: Some("Términos relacionados"),
+    coordinate_terms: Some("Cohipónimos"),
+    wiktionary: Some("Wikcionario"),
+    redirected_from: Some("redirigido desde {word}"),
+    or: Some("o"),
+};
+
+const FR: Locale = Locale {
+    example: Some("1 exemple"),
+    examples: Some("{n} exemples"),
+    grammar: Some("Grammaire"),
+    etymology: Some("Étymologie"),
+    usage_notes: Some("Notes d'usage"),
+    synonyms: Some("Synonymes"),
+    antonyms: Some("Antonymes"),
+    hypernyms: Some("Hyperonymes"),
+    derived_terms: Some("Dérivés"),
+    related_terms: Some("Apparentés"),
+    coordinate_terms: Some("Cohyponymes"),
+    wiktionary: Some("Wiktionnaire"),
+    redirected_from: Some("redirigé depuis {word}"),
+    or: Some("ou"),
+};
+
+const ID: Locale = Locale {
+    example: Some("1 contoh"),
+    examples: Some("{n} contoh"),
+    grammar: Some("Tata bahasa"),
+    etymology: Some("Etimologi"),
+    usage_notes: Some("Catatan penggunaan"),
+    synonyms: Some("Sinonim"),
+    antonyms: Some("Antonim"),
+    hypernyms: Some("Hipernim"),
+    derived_terms: Some("Kata turunan"),
+    related_terms: Some("Istilah terkait"),
+    wiktionary: Some("Wikikamus"),
+    redirected_from: Some("dialihkan dari {word}"),
+    or: Some("atau"),
+    ..MISSING
+};
+
+const IT: Locale = Locale {
+    example: Some("1 esempio"),
+    examples: Some("{n} esempi"),
+    grammar: Some("Grammatica"),
+    etymology: Some("Etimologia"),
+    usage_notes: Some("Note d'uso"),
+    synonyms: Some("Sinonimi"),
+    antonyms: Some("Contrari"),
+    hypernyms: Some("Iperonimi"),
+    derived_terms: Some("Termini derivati"),
+    related_terms: Some("Termini correlati"),
+    coordinate_terms: Some("Coiponimi"),
+    wiktionary: Some("Wikizionario"),
+    redirected_from: Some("reindirizzato da {word}"),
+    or: Some("o"),
+};
+
+const JA: Locale = Locale {
+    example: Some("1 例"),
+    examples: Some("{n} 例"),
+    grammar: Some("文法"),
+    etymology: Some("語源"),
+    usage_notes: Some("用法"),
+    synonyms: Some("類義語"),
+    antonyms: Some("対義語"),
+    hypernyms: Some("上位語"),
+    derived_terms: Some("派生語"),
+    related_terms: Some("関連語"),
+    coordinate_terms: Some("同位語"),
+    wiktionary: Some("ウィクショナリー"),
+    redirected_from: Some("{word}からの転送"),
+    or: Some("または"),
+};
 
-// This should be done differently, and support every section of the dictionary (i.e. Etymology)
+const KO: Locale = Locale {
+    example: Some("예문 1개"),
+    examples: Some("예문 {n}개"),
+    grammar: Some("문법"),
+    etymology: Some("어원"),
+    usage_notes: Some("용법"),
+    synonyms: Some("유의어"),
+    antonyms: Some("반의어"),
+    hypernyms: Some("상위어"),
+    derived_terms: Some("파생어"),
+    related_terms: Some("관련어"),
+    wiktionary: Some("위키낱말사전"),
+    redirected_from: Some("{word}에서 넘어옴"),
+    or: Some("또는"),
+    ..MISSING
+};
+
+const MS: Locale = Locale {
+    example: Some("1 contoh"),
+    examples: Some("{n} contoh"),
+    grammar: Some("Tatabahasa"),
+    etymology: Some("Etimologi"),
+    usage_notes: Some("Nota penggunaan"),
+    synonyms: Some("Sinonim"),
+    antonyms: Some("Antonim"),
+    hypernyms: Some("Hipernim"),
+    derived_terms: Some("Kata terbitan"),
+    related_terms: Some("Istilah berkaitan"),
+    wiktionary: Some("Wikikamus"),
+    redirected_from: Some("dilencongkan dari {word}"),
+    or: Some("atau"),
+    ..MISSING
+};
+
+const NL: Locale = Locale {
+    example: Some("1 voorbeeld"),
+    examples: Some("{n} voorbeelden"),
+    grammar: Some("Grammatica"),
+    etymology: Some("Etymologie"),
+    usage_notes: Some("Gebruik"),
+    synonyms: Some("Synoniemen"),
+    antonyms: Some("Antoniemen"),
+    hypernyms: Some("Hyperoniemen"),
+    derived_terms: Some("Afgeleide woorden"),
+    related_terms: Some("Verwante woorden"),
+    wiktionary: Some("WikiWoordenboek"),
+    redirected_from: Some("doorverwezen van {word}"),
+    or: Some("of"),
+    ..MISSING
+};
+
+const PL: Locale = Locale {
+    example: Some("1 przykład"),
+    examples: Some("{n} przykłady"),
+    grammar: Some("Gramatyka"),
+    etymology: Some("Etymologia"),
+    usage_notes: Some("Uwagi"),
+    synonyms: Some("Synonimy"),
+    antonyms: Some("Antonimy"),
+    hypernyms: Some("Hiperonimy"),
+    derived_terms: Some("Wyrazy pochodne"),
+    related_terms: Some("Wyrazy pokrewne"),
+    coordinate_terms: Some("Kohiponimy"),
+    wiktionary: Some("Wikisłownik"),
+    redirected_from: Some("przekierowano z {word}"),
+    or: Some("lub"),
+};
+
+const PT: Locale = Locale {
+    example: Some("1 exemplo"),
+    examples: Some("{n} exemplos"),
+    grammar: Some("Gramática"),
+    etymology: Some("Etimologia"),
+    usage_notes: Some("Notas de uso"),
+    synonyms: Some("Sinônimos"),
+    antonyms: Some("Antônimos"),
+    hypernyms: Some("Hiperônimos"),
+    derived_terms: Some("Termos derivados"),
+    related_terms: Some("Termos relacionados"),
+    coordinate_terms: Some("Co-hipônimos"),
+    wiktionary: Some("Wikcionário"),
+    redirected_from: Some("redirecionado de {word}"),
+    or: Some("ou"),
+};
+
+const RU: Locale = Locale {
+    example: Some("1 пример"),
+    examples: Some("{n} примеры"),
+    grammar: Some("Грамматика"),
+    etymology: Some("Этимология"),
+    usage_notes: Some("Употребление"),
+    synonyms: Some("Синонимы"),
+    antonyms: Some("Антонимы"),
+    hypernyms: Some("Гиперонимы"),
+    derived_terms: Some("Производные"),
+    related_terms: Some("Родственные слова"),
+    coordinate_terms: Some("Согипонимы"),
+    wiktionary: Some("Викисловарь"),
+    redirected_from: Some("перенаправлено с {word}"),
+    or: Some("или"),
+};
+
+const TH: Locale = Locale {
+    example: Some("ตัวอย่าง 1 รายการ"),
+    examples: Some("ตัวอย่าง {n} รายการ"),
+    grammar: Some("ไวยากรณ์"),
+    etymology: Some("รากศัพท์"),
+    synonyms: Some("คำพ้องความหมาย"),
+    antonyms: Some("คำตรงข้าม"),
+    related_terms: Some("คำที่เกี่ยวข้อง"),
+    wiktionary: Some("วิกิพจนานุกรม"),
+    or: Some("หรือ"),
+    ..MISSING
+};
+
+const TR: Locale = Locale {
+    example: Some("1 örnek"),
+    examples: Some("{n} örnek"),
+    grammar: Some("Dil bilgisi"),
+    etymology: Some("Köken"),
+    usage_notes: Some("Kullanım notları"),
+    synonyms: Some("Eş anlamlılar"),
+    antonyms: Some("Zıt anlamlılar"),
+    hypernyms: Some("Üst anlamlılar"),
+    derived_terms: Some("Türetilmiş kelimeler"),
+    related_terms: Some("İlgili kelimeler"),
+    wiktionary: Some("Vikisözlük"),
+    redirected_from: Some("{word} sayfasından yönlendirildi"),
+    or: Some("veya"),
+    ..MISSING
+};
+
+const VI: Locale = Locale {
+    example: Some("1 ví dụ"),
+    examples: Some("{n} ví dụ"),
+    grammar: Some("Ngữ pháp"),
+    etymology: Some("Từ nguyên"),
+    usage_notes: Some("Ghi chú sử dụng"),
+    synonyms: Some("Từ đồng nghĩa"),
+    antonyms: Some("Từ trái nghĩa"),
+    derived_terms: Some("Từ dẫn xuất"),
+    related_terms: Some("Từ liên quan"),
+    wiktionary: Some("Wiktionary"),
+    redirected_from: Some("đổi hướng từ {word}"),
+    or: Some("hoặc"),
+    ..MISSING
+};
+
+const ZH: Locale = Locale {
+    example: Some("1 例"),
+    examples: Some("{n} 例"),
+    grammar: Some("语法"),
+    etymology: Some("词源"),
+    usage_notes: Some("用法说明"),
+    synonyms: Some("同义词"),
+    antonyms: Some("反义词"),
+    hypernyms: Some("上位词"),
+    derived_terms: Some("派生词"),
+    related_terms: Some("相关词"),
+    coordinate_terms: Some("同类词"),
+    wiktionary: Some("维基词典"),
+    redirected_from: Some("重定向自{word}"),
+    or: Some("或"),
+};
+
+const fn locale(edition: EditionLang) -> &'static Locale {
+    match edition {
+        EditionLang::En | EditionLang::Simple => &EN,
+        EditionLang::Cs => &CS,
+        EditionLang::De => &DE,
+        EditionLang::El => &EL,
+        EditionLang::Es => &ES,
+        EditionLang::Fr => &FR,
+        EditionLang::Id => &ID,
+        EditionLang::It => &IT,
+        EditionLang::Ja => &JA,
+        EditionLang::Ko => &KO,
+        EditionLang::Ku => &MISSING,
+        EditionLang::Ms => &MS,
+        EditionLang::Nl => &NL,
+        EditionLang::Pl => &PL,
+        EditionLang::Pt => &PT,
+        EditionLang::Ru => &RU,
+        EditionLang::Th => &TH,
+        EditionLang::Tr => &TR,
+        EditionLang::Vi => &VI,
+        EditionLang::Zh => &ZH,
+    }
+}
+
+/// The label in the language of the edition, or in English if there is no translation.
+pub fn localize(edition: EditionLang, label: Label) -> &'static str {
+    locale(edition)
+        .get(label)
+        .unwrap_or_else(|| label.english())
+}
+
+/// The labels that fall back to English for this edition.
+pub fn missing_translations(edition: EditionLang) -> impl Iterator<Item = Label> {
+    let locale = locale(edition);
+    Label::ALL
+        .into_iter()
+        .filter(|&label| locale.get(label).is_none())
+}
 
 pub fn localize_examples_string(edition: EditionLang, n: usize) -> String {
-    let (singular, plural) = match edition {
-        EditionLang::Fr => ("exemple", "exemples"),
-        EditionLang::De => ("Beispiel", "Beispiele"),
-        EditionLang::Es => ("ejemplo", "ejemplos"),
-        EditionLang::Ru => ("пример", "примеры"),
-        EditionLang::Zh | EditionLang::Ja => return format!("{n} 例"), // special case
-        _ => ("example", "examples"),
+    let label = if n == 1 {
+        Label::Example
+    } else {
+        Label::Examples
     };
+    localize(edition, label).replace("{n}", &n.to_string())
+}
 
-    if n == 1 {
-        format!("1 {singular}")
-    } else {
-        format!("{n} {plural}")
+pub fn localize_redirected_from(edition: EditionLang, word: &str) -> String {
+    localize(edition, Label::RedirectedFrom).replace("{word}", word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn examples_string() {
+        assert_eq!(localize_examples_string(EditionLang::En, 1), "1 example");
+        assert_eq!(localize_examples_string(EditionLang::Fr, 3), "3 exemples");
+        assert_eq!(localize_examples_string(EditionLang::Ja, 2), "2 例");
+    }
+
+    #[test]
+    fn fallback_to_english() {
+        assert_eq!(localize(EditionLang::Ku, Label::Etymology), "Etymology");
+        assert!(missing_translations(EditionLang::Ku).any(|label| label == Label::Etymology));
+        assert_eq!(missing_translations(EditionLang::En).count(), 0);
+        assert_eq!(
+            localize_redirected_from(EditionLang::De, "ging"),
+            "weitergeleitet von ging"
+        );
     }
 }
@@ -1,4 +1,4 @@
-use std::{fs::File, io::BufWriter, sync::LazyLock};
+use std::{fs::File, hash::Hasher, io::BufWriter, sync::LazyLock};
 
 use anyhow::Result;
 use regex::Regex;
@@ -9,23 +9,30 @@ use crate::{
     Map, Set,
     cli::Options,
     dict::{
-        Diagnostics, Dictionary, Intermediate, LabelledYomitanEntry,
-        locale::localize_examples_string,
+        Diagnostics, Dictionary, HeadInfoStrategy, InflectionMatch, Intermediate,
+        LabelledYomitanEntry, ReadingStrategy, TagPropagation, TagSource,
+        locale::{
+            Label, localize, localize_examples_string, localize_redirected_from,
+            missing_translations,
+        },
+        rules,
     },
     lang::{EditionLang, Lang},
     models::{
-        kaikki::{Example, Form, HeadTemplate, Pos, Sense, Tag, WordEntry},
+        kaikki::{
+            Example, ExamplePreference, Form, HeadTemplate, LinkageKind, Pos, Sense, Tag, WordEntry,
+        },
         yomitan::{
-            BacklinkContent, DetailedDefinition, GenericNode, Ipa, NTag, Node, NodeData, TermBank,
-            YomitanEntry, wrap,
+            BacklinkContent, DetailedDefinition, GenericNode, Ipa, LinkContent, NTag, Node,
+            NodeData, TermBank, YomitanEntry, wrap,
         },
     },
-    path::PathManager,
+    path::{DictionaryType, PathManager},
     tags::{
-        REDUNDANT_FORM_TAGS, find_short_pos, find_tag_in_bank, merge_person_tags,
-        remove_redundant_tags, sort_tags, sort_tags_by_similar,
+        BLACKLISTED_FORM_TAGS, REDUNDANT_FORM_TAGS, find_short_pos, find_tag_in_bank,
+        merge_person_tags, remove_redundant_tags, sort_tags, sort_tags_by_similar,
     },
-    utils::{link_kaikki, link_wiktionary, pretty_println_at_path},
+    utils::{Fnv1a, link_kaikki, link_wiktionary, link_yomitan_search, pretty_println_at_path},
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -36,13 +43,33 @@ impl Intermediate for Tidy {
         self.len()
     }
 
+    fn merge(&mut self, other: Self) {
+        self.merge(other);
+    }
+
     fn write(&self, pm: &PathManager, options: &Options) -> Result<()> {
         self.write(options, pm)
     }
+
+    fn to_cache(&self) -> Result<String> {
+        let lemmas: Vec<_> = self.lemma_map.0.iter().collect();
+        let forms: Vec<_> = self.form_map.0.iter().collect();
+        Ok(serde_json::to_string(&(lemmas, forms))?)
+    }
+
+    fn from_cache(cache: &str) -> Result<Self> {
+        let (lemmas, forms): TidyCache = serde_json::from_str(cache)?;
+        Ok(Self {
+            lemma_map: LemmaMap(lemmas.into_iter().collect()),
+            form_map: FormMap(forms.into_iter().collect()),
+        })
+    }
 }
 
 impl Dictionary for DMain {
     type I = Tidy;
+    const DICT_TYPE: DictionaryType = DictionaryType::Main;
+    const MUTATES_ENTRIES: bool = true;
 
     fn preprocess(
         &self,
@@ -71,7 +98,7 @@ impl Dictionary for DMain {
         postprocess_forms(&mut irs.form_map);
     }
 
-    fn found_ir_message(&self, irs: &Self::I) {
+    fn found_ir_message(&self, irs: &Self::I) -> String {
         // A bit hacky to have it here
         let n_lemmas = irs.lemma_map.len();
         let n_forms = irs.form_map.len();
@@ -84,10 +111,10 @@ impl Dictionary for DMain {
             "mismatch in form counts"
         );
         let n_entries = n_lemmas + n_forms;
-        println!(
+        format!(
             "Found {n_entries} entries: {n_lemmas} lemmas, {n_forms} forms \
 ({n_forms_inflection} inflections, {n_forms_extracted} extracted, {n_forms_alt_of} alt_of)"
-        );
+        )
     }
 
     fn write_ir(&self) -> bool {
@@ -98,15 +125,21 @@ impl Dictionary for DMain {
         &self,
         edition: EditionLang,
         source: Lang,
-        _: Lang,
+        target: Lang,
         options: &Options,
         diagnostics: &mut Diagnostics,
         irs: Self::I,
     ) -> Vec<LabelledYomitanEntry> {
+        if options.save_temps {
+            for label in missing_translations(edition) {
+                diagnostics.increment_missing_translation(format!("{label:?}"), edition);
+            }
+        }
+
         vec![
             (
                 "lemma",
-                to_yomitan_lemmas(edition, options, irs.lemma_map, diagnostics),
+                to_yomitan_lemmas(edition, target, options, irs.lemma_map, diagnostics),
             ),
             ("form", to_yomitan_forms(source, irs.form_map)),
         ]
@@ -117,9 +150,10 @@ impl Dictionary for DMain {
     }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 struct LemmaKey {
     lemma: String,
+    /// The main reading. Cf. `get_readings`.
     reading: String,
     pos: Pos,
 }
@@ -156,15 +190,17 @@ impl LemmaMap {
     }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 struct FormKey {
     uninflected: String,
     inflected: String,
     pos: Pos,
 }
 
+type FormInfo = (FormSource, Vec<String>);
+
 #[derive(Debug, Default)]
-struct FormMap(Map<FormKey, (FormSource, Vec<String>)>);
+struct FormMap(Map<FormKey, FormInfo>);
 
 // We only serialize for debugging in the testsuite, so having this tmp nested is easy to write and
 // has no overhead when building the dictionary without --save-temps. This way, we avoid storing
@@ -174,8 +210,7 @@ impl Serialize for FormMap {
     where
         S: serde::Serializer,
     {
-        let mut nested: Map<&str, Map<&str, Map<&str, &(FormSource, Vec<String>)>>> =
-            Map::default();
+        let mut nested: Map<&str, Map<&str, Map<&str, &FormInfo>>> = Map::default();
 
         for (key, infos) in &self.0 {
             nested
@@ -260,11 +295,31 @@ enum FormSource {
 struct LemmaInfo {
     gloss_tree: GlossTree,
 
+    /// Readings other than the main one (ex. 行: háng), that share the definitions.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    alt_readings: Vec<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     etymology_text: Option<String>,
 
+    /// Words of the source language mentioned in the etymology, that link to their own entry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    etymology_mentions: Vec<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
-    head_info_text: Option<String>,
+    head_info: Option<HeadInfo>,
+
+    /// Words of the entry and of its senses, for every `--linkages` kind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    linkages: Vec<(LinkageKind, Vec<String>)>,
+
+    /// Usage notes of the entry.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<String>,
+
+    /// Word level tags and topics (ex. archaic), as opposed to the tags of each sense.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    term_tags: Vec<Tag>,
 
     #[serde(rename = "wlink")]
     link_wiktionary: String,
@@ -273,6 +328,20 @@ struct LemmaInfo {
     link_kaikki: String,
 }
 
+/// Cf. `HeadInfoStrategy`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(untagged)]
+enum HeadInfo {
+    Text(String),
+    Forms {
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        gender: Vec<Tag>,
+        /// (Tags, forms) in order of appearance. Ex. ("genitive", ["Herzens", "Herzes"])
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        forms: Vec<(String, Vec<String>)>,
+    },
+}
+
 type GlossTree = Map<String, GlossInfo>;
 
 // ... its really SenseInfo but oh well
@@ -288,6 +357,13 @@ struct GlossInfo {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     examples: Vec<Example>,
 
+    #[serde(skip_serializing_if = "String::is_empty")]
+    qualifier: String,
+
+    /// Cf. `Sense::raw_tags`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    raw_tags: Vec<Tag>,
+
     #[serde(skip_serializing_if = "Map::is_empty")]
     children: GlossTree,
 }
@@ -299,18 +375,37 @@ pub struct Tidy {
     form_map: FormMap,   // 56
 }
 
+/// Flat `Tidy`, for the `--incremental` cache: the maps only serialize to nested debug views.
+type TidyCache = (Vec<(LemmaKey, Vec<LemmaInfo>)>, Vec<(FormKey, FormInfo)>);
+
 impl Tidy {
     fn len(&self) -> usize {
         self.lemma_map.len() + self.form_map.len()
     }
 
+    /// Same as inserting every lemma and form of `other`, in order.
+    fn merge(&mut self, other: Self) {
+        for (key, infos) in other.lemma_map.0 {
+            self.lemma_map.0.entry(key).or_default().extend(infos);
+        }
+
+        for (key, (source, tags)) in other.form_map.0 {
+            self.form_map
+                .0
+                .entry(key)
+                .or_insert_with(|| (source, Vec::new()))
+                .1
+                .extend(tags);
+        }
+    }
+
     // This is usually called at the end, so it could just move the arguments...
-    fn insert_lemma(&mut self, lemma: &str, reading: &str, pos: &str, entry: LemmaInfo) {
+    fn insert_lemma(&mut self, lemma: &str, reading: String, pos: &str, entry: LemmaInfo) {
         debug_assert!(!entry.gloss_tree.is_empty());
 
         let key = LemmaKey {
             lemma: lemma.into(),
-            reading: reading.into(),
+            reading,
             pos: pos.into(),
         };
 
@@ -397,17 +492,20 @@ fn postprocess_forms(form_map: &mut FormMap) {
 fn process_main(edition: EditionLang, source: Lang, word_entry: &WordEntry, irs: &mut Tidy) {
     process_forms(edition, source, word_entry, irs);
 
+    if rules().edition(edition).reading(source) == ReadingStrategy::Chinese {
+        process_chinese_variants(word_entry, irs);
+    }
+
     process_alt_forms(word_entry, irs);
 
     if word_entry.contains_no_gloss() {
         process_no_gloss(edition, word_entry, irs);
     } else {
-        irs.insert_lemma(
-            &word_entry.word,
-            &get_reading(edition, source, word_entry).unwrap_or_else(|| word_entry.word.clone()),
-            &word_entry.pos,
-            process_word_entry(edition, source, word_entry),
-        );
+        let mut readings = get_readings(edition, source, word_entry).into_iter();
+        let reading = readings.next().unwrap_or_else(|| word_entry.word.clone());
+        let mut info = process_word_entry(edition, source, word_entry);
+        info.alt_readings = readings.collect();
+        irs.insert_lemma(&word_entry.word, reading, &word_entry.pos, info);
     }
 }
 
@@ -420,47 +518,8 @@ fn preprocess_main(
     irs: &mut Tidy,
 ) {
     // WARN: mutates word_entry::senses::sense::tags
-    match edition {
-        EditionLang::En => {
-            // The original fetched them from head_templates but it is better not to touch that
-            // and we can do the same by looking at the tags of the canonical form.
-            if let Some(cform) = word_entry.canonical_form() {
-                let cform_tags: Vec<_> = cform.tags.clone();
-                for sense in &mut word_entry.senses {
-                    for tag in &cform_tags {
-                        if tag != "canonical" && !sense.tags.contains(tag) {
-                            sense.tags.push(tag.into());
-                        }
-                    }
-                }
-            }
-        }
-        EditionLang::El => {
-            // Fetch gender from a matching form
-            let gender_tags = ["masculine", "feminine", "neuter"];
-            for form in &word_entry.forms {
-                if form.form == word_entry.word {
-                    for sense in &mut word_entry.senses {
-                        for tag in &form.tags {
-                            if gender_tags.contains(&tag.as_str()) && !sense.tags.contains(tag) {
-                                sense.tags.push(tag.into());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        EditionLang::Ru => {
-            // Propagate word_entry.tags to sense.tags
-            for sense in &mut word_entry.senses {
-                for tag in &word_entry.tags {
-                    if !sense.tags.contains(tag) {
-                        sense.tags.push(tag.into());
-                    }
-                }
-            }
-        }
-        _ => (),
+    for propagation in rules().edition(edition).propagate_tags() {
+        propagate_tags(propagation, word_entry);
     }
 
     // WARN: mutates word_entry::senses
@@ -489,10 +548,10 @@ fn preprocess_main(
     let old_senses = std::mem::take(&mut word_entry.senses);
     let mut senses_without_inflections = Vec::new();
     for sense in old_senses {
-        if is_inflection_sense(edition, &sense)
-            && (!options.experimental || word_entry.non_trivial_forms().next().is_none())
-        {
-            handle_inflection_sense(edition, source, word_entry, &sense, irs);
+        let inflection = match_inflection_sense(edition, &sense)
+            .filter(|_| !options.experimental || word_entry.non_trivial_forms().next().is_none());
+        if let Some(inflection) = inflection {
+            handle_inflection_sense(edition, source, word_entry, &sense, inflection, irs);
         } else {
             senses_without_inflections.push(sense);
         }
@@ -515,6 +574,48 @@ fn preprocess_main(
             }
         }
     }
+
+    // WARN: mutates word_entry::senses::sense::examples
+    for sense in &mut word_entry.senses {
+        filter_examples(options, &mut sense.examples);
+    }
+
+    // WARN: mutates word_entry::linkages and word_entry::senses::sense::linkages
+    //
+    // Drop the linkages that were not asked for, so that process_word_entry just takes the rest.
+    for kind in LinkageKind::ALL {
+        if !options.linkages.contains(&kind) {
+            word_entry.linkages_mut(kind).clear();
+            for sense in &mut word_entry.senses {
+                sense.linkages_mut(kind).clear();
+            }
+        }
+    }
+}
+
+/// Copy the tags of `propagation.from` to every sense.
+fn propagate_tags(propagation: &TagPropagation, word_entry: &mut WordEntry) {
+    let source_tags: Vec<Tag> = match propagation.from {
+        TagSource::CanonicalForm => word_entry
+            .canonical_form()
+            .map(|cform| cform.tags.clone())
+            .unwrap_or_default(),
+        TagSource::WordForms => word_entry
+            .forms
+            .iter()
+            .filter(|form| form.form == word_entry.word)
+            .flat_map(|form| form.tags.iter().cloned())
+            .collect(),
+        TagSource::Entry => word_entry.tags.clone(),
+    };
+
+    for sense in &mut word_entry.senses {
+        for tag in &source_tags {
+            if propagation.accepts(tag) && !sense.tags.contains(tag) {
+                sense.tags.push(tag.into());
+            }
+        }
+    }
 }
 
 /// Add Extracted forms. That is, forms from `word_entry.forms`.
@@ -534,6 +635,13 @@ fn process_forms(edition: EditionLang, source: Lang, word_entry: &WordEntry, irs
             break;
         }
 
+        // Cf. process_chinese_variants
+        if chinese_variant_tags(form).is_some()
+            && rules().edition(edition).reading(source) == ReadingStrategy::Chinese
+        {
+            continue;
+        }
+
         irs.insert_form(
             &word_entry.word,
             &form.form,
@@ -544,25 +652,54 @@ fn process_forms(edition: EditionLang, source: Lang, word_entry: &WordEntry, irs
     }
 }
 
-// Finnish from the English edition crashes with out-of-memory.
-// There are simply too many forms, so we prune the less used (possessive).
-//
-// https://uusikielemme.fi/finnish-grammar/possessive-suffixes-possessiivisuffiksit#one
+/// Cf. `EditionRules::break_at_forms`.
 fn should_break_at_finish_forms(edition: EditionLang, source: Lang, form: &Form) -> bool {
-    if matches!((edition, source), (EditionLang::En, Lang::Fi)) {
-        // HACK: 1. For tables that parse the title
-        // https://kaikki.org/dictionary/Finnish/meaning/p/p%C3%A4/p%C3%A4%C3%A4.html
-        if form.form == "See the possessive forms below." {
-            return true;
-        }
-        // HACK: 2. For tables that don't parse the title
-        // https://kaikki.org/dictionary/Finnish/meaning/i/is/iso.html
-        // https://github.com/tatuylonen/wiktextract/issues/1565
-        if form.form == "Rare. Only used with substantive adjectives." {
-            return true;
+    rules()
+        .edition(edition)
+        .break_at_forms(source)
+        .contains(&form.form)
+}
+
+/// Return the tags of the variant (traditional or simplified) of `form`, and of its counterpart.
+fn chinese_variant_tags(form: &Form) -> Option<(&'static str, &'static str)> {
+    const TRADITIONAL: &str = "Traditional-Chinese";
+    const SIMPLIFIED: &str = "Simplified-Chinese";
+
+    form.tags.iter().find_map(|tag| match tag.as_str() {
+        TRADITIONAL => Some((TRADITIONAL, SIMPLIFIED)),
+        SIMPLIFIED => Some((SIMPLIFIED, TRADITIONAL)),
+        _ => None,
+    })
+}
+
+/// Make traditional and simplified variants redirect to the entry that holds the definitions.
+///
+/// Usually the entry of one variant has the glosses, while the other is a soft redirect with no
+/// glosses (ex. 电脑 → 電腦). In the latter case, the redirection goes the other way around.
+fn process_chinese_variants(word_entry: &WordEntry, irs: &mut Tidy) {
+    for form in word_entry.non_trivial_forms() {
+        let Some((variant_tag, word_tag)) = chinese_variant_tags(form) else {
+            continue;
+        };
+
+        if word_entry.contains_no_gloss() {
+            irs.insert_form(
+                &form.form,
+                &word_entry.word,
+                &word_entry.pos,
+                FormSource::AltOf,
+                vec![word_tag.to_string()],
+            );
+        } else {
+            irs.insert_form(
+                &word_entry.word,
+                &form.form,
+                &word_entry.pos,
+                FormSource::Extracted,
+                vec![variant_tag.to_string()],
+            );
         }
     }
-    false
 }
 
 /// Add `AltOf` forms. That is, alternative forms.
@@ -597,55 +734,117 @@ fn process_alt_forms(word_entry: &WordEntry, irs: &mut Tidy) {
 
 /// Process "no-gloss" word entries for alternative ways of adding lemmas/forms.
 fn process_no_gloss(edition: EditionLang, word_entry: &WordEntry, irs: &mut Tidy) {
-    match edition {
-        // Unfortunately we are in the same A from B, B from C situation discussed in
-        // preprocess_word_entry. There is no easy solution for adding the lemma back because at
-        // this point the gloss has been deleted. Maybe reconsider the original approach of
-        // deleting glosses, and mark them somehow as "inflection-only".
-        //
-        // At any rate, this will still add useful redirections.
-        EditionLang::El => {
-            if word_entry.is_participle()
-                && let Some(form_of) = word_entry.form_of.first()
-            {
-                irs.insert_form(
-                    &form_of.word,
-                    &word_entry.word,
-                    &word_entry.pos,
-                    FormSource::Inflection,
-                    vec![format!("redirected from {}", word_entry.word)],
-                );
-            }
-        }
-        _ => (),
+    // Unfortunately we are in the same A from B, B from C situation discussed in
+    // preprocess_word_entry. There is no easy solution for adding the lemma back because at
+    // this point the gloss has been deleted. Maybe reconsider the original approach of
+    // deleting glosses, and mark them somehow as "inflection-only".
+    //
+    // At any rate, this will still add useful redirections.
+    if edition == EditionLang::El
+        && word_entry.is_participle()
+        && let Some(form_of) = word_entry.form_of.first()
+    {
+        irs.insert_form(
+            &form_of.word,
+            &word_entry.word,
+            &word_entry.pos,
+            FormSource::Inflection,
+            vec![localize_redirected_from(edition, &word_entry.word)],
+        );
     }
 }
 
-// There are potentially more than one, but yomitan doesn't really support it
+/// Return the main reading, cf. `get_readings`.
 pub fn get_reading(edition: EditionLang, source: Lang, word_entry: &WordEntry) -> Option<String> {
-    match (edition, source) {
-        (EditionLang::En, Lang::Ja) => get_japanese_reading(word_entry),
-        (EditionLang::En, Lang::Fa) => word_entry.romanization_form().map(|f| f.form.clone()),
-        (EditionLang::Ja, _) => word_entry.transliteration_form().map(|f| f.form.clone()),
-        (EditionLang::En | EditionLang::Zh, Lang::Zh) => word_entry.pinyin().map(String::from),
-        _ => get_canonical_word(source, word_entry),
-    }
+    get_readings(edition, source, word_entry).into_iter().next()
 }
 
-/// The canonical word may contain extra diacritics.
+/// Return every reading of the word, without duplicates. The first one is the main reading.
 ///
-/// For most languages, this is equal to word, but for, let's say, Latin, there may be a
-/// difference (cf. <https://en.wiktionary.org/wiki/fama>, where `word_entry.word` is fama, but
-/// this will return fāma).
+/// For example, a Japanese word with multiple canonical forms, a Chinese word with multiple pinyin,
+/// or a Persian word with multiple romanizations.
+pub fn get_readings(edition: EditionLang, source: Lang, word_entry: &WordEntry) -> Vec<String> {
+    let readings: Vec<String> = match rules().edition(edition).reading(source) {
+        ReadingStrategy::Canonical => get_canonical_word(source, word_entry).into_iter().collect(),
+        ReadingStrategy::Japanese => get_japanese_readings(word_entry),
+        ReadingStrategy::Romanization => word_entry
+            .romanization_forms()
+            .map(|f| f.form.clone())
+            .collect(),
+        ReadingStrategy::Transliteration => word_entry
+            .transliteration_forms()
+            .map(|f| f.form.clone())
+            .collect(),
+        ReadingStrategy::Chinese => get_chinese_readings(source, word_entry),
+        ReadingStrategy::None => Vec::new(),
+    };
+
+    let mut seen = Set::default();
+    readings
+        .into_iter()
+        .filter(|reading| seen.insert(reading.clone()))
+        .collect()
+}
+
+/// The canonical word may contain extra diacritics. Cf. `CommonRules::canonical_sources`.
 fn get_canonical_word(source: Lang, word_entry: &WordEntry) -> Option<String> {
-    match source {
-        Lang::La | Lang::Ru | Lang::Grc => word_entry.canonical_form().map(|f| f.form.to_string()),
-        _ => None,
+    if rules().common.is_canonical_source(source) {
+        word_entry.canonical_form().map(|f| f.form.to_string())
+    } else {
+        None
     }
 }
 
-// Does not support multiple readings
-fn get_japanese_reading(word_entry: &WordEntry) -> Option<String> {
+/// Return the Jyutping for Cantonese, and the tone-marked Standard Mandarin pinyin otherwise.
+///
+/// For example, 行: xíng, háng. Sounds are not tied to senses in kaikki, so every reading gets
+/// all the senses of the entry.
+fn get_chinese_readings(source: Lang, word_entry: &WordEntry) -> Vec<String> {
+    if source == Lang::Yue {
+        return word_entry
+            .tagged_zh_sounds(&["Cantonese", "Jyutping"])
+            // Yale is sometimes tagged as Jyutping in the zh edition
+            .filter(|sound| !sound.tags.iter().any(|t| t == "Yale"))
+            .map(|sound| sound.zh_pron.clone())
+            .collect();
+    }
+
+    word_entry
+        .tagged_zh_sounds(&["Mandarin", "Pinyin"])
+        .filter(|sound| {
+            sound
+                .tags
+                .iter()
+                .any(|t| t == "Standard" || t == "Standard-Chinese")
+                && !sound
+                    .tags
+                    .iter()
+                    .any(|t| t == "toneless-final-syllable-variant")
+        })
+        // The en edition appends the numbered pinyin: "tián (tian²)"
+        .map(|sound| match sound.zh_pron.split_once(" (") {
+            Some((pinyin, _)) => pinyin.to_string(),
+            None => sound.zh_pron.clone(),
+        })
+        // Numbered pinyin (tian²) are not tone-marked
+        .filter(|pinyin| {
+            !pinyin
+                .chars()
+                .any(|c| c.is_ascii_digit() || "¹²³⁴⁵⁰".contains(c))
+        })
+        .collect()
+}
+
+/// Return one reading per canonical form with ruby (ex. 狸: たぬき, タヌキ).
+fn get_japanese_readings(word_entry: &WordEntry) -> Vec<String> {
+    word_entry
+        .canonical_forms()
+        .filter(|cform| !cform.ruby.is_empty())
+        .filter_map(|cform| get_japanese_reading(word_entry, cform))
+        .collect()
+}
+
+fn get_japanese_reading(word_entry: &WordEntry, cform: &Form) -> Option<String> {
     // The original parses head_templates directly (which probably deserves a PR to
     // wiktextract), although imo pronunciation templates should have been better.
     // There is no pronunciation template info in en-wiktextract, and while I think that
@@ -663,55 +862,66 @@ fn get_japanese_reading(word_entry: &WordEntry) -> Option<String> {
     // }
 
     // I really don't want to touch templates so instead, replace the ruby
-    if let Some(cform) = word_entry.canonical_form()
-        && !cform.ruby.is_empty()
-    {
-        // https://github.com/tatuylonen/wiktextract/issues/1484
-        // let mut cform_lemma = cform.form.clone();
-        // if cform_lemma != word_entry.word {
-        //     warn!(
-        //         "Canonical form: '{cform_lemma}' != word: '{}'\n{}\n{}\n\n",
-        //         word_entry.word,
-        //         link_wiktionary(args, &word_entry.word),
-        //         link_kaikki(args, &word_entry.word),
-        //     );
-        // } else {
-        //     warn!(
-        //         "Equal for word: '{}'\n{}\n{}\n\n",
-        //         word_entry.word,
-        //         link_wiktionary(args, &word_entry.word),
-        //         link_kaikki(args, &word_entry.word),
-        //     );
-        // }
-
-        // This should be cform.form, but it's not parsed properly:
-        // https://github.com/tatuylonen/wiktextract/issues/1484
-        let mut cform_lemma = word_entry.word.clone();
-        let mut cursor = 0;
-        for (base, reading) in &cform.ruby {
-            if let Some(pos) = cform_lemma[cursor..].find(base) {
-                let start = cursor + pos;
-                let end = start + base.len();
-                cform_lemma.replace_range(start..end, reading);
-                cursor = start + reading.len();
-            } else {
-                tracing::warn!("Kanji '{}' not found in '{}'", base, cform_lemma);
-                return None;
-            }
+    // https://github.com/tatuylonen/wiktextract/issues/1484
+    // let mut cform_lemma = cform.form.clone();
+    // if cform_lemma != word_entry.word {
+    //     warn!(
+    //         "Canonical form: '{cform_lemma}' != word: '{}'\n{}\n{}\n\n",
+    //         word_entry.word,
+    //         link_wiktionary(args, &word_entry.word),
+    //         link_kaikki(args, &word_entry.word),
+    //     );
+    // } else {
+    //     warn!(
+    //         "Equal for word: '{}'\n{}\n{}\n\n",
+    //         word_entry.word,
+    //         link_wiktionary(args, &word_entry.word),
+    //         link_kaikki(args, &word_entry.word),
+    //     );
+    // }
+
+    // This should be cform.form, but it's not parsed properly:
+    // https://github.com/tatuylonen/wiktextract/issues/1484
+    let mut cform_lemma = word_entry.word.clone();
+    let mut cursor = 0;
+    for (base, reading) in &cform.ruby {
+        if let Some(pos) = cform_lemma[cursor..].find(base) {
+            let start = cursor + pos;
+            let end = start + base.len();
+            cform_lemma.replace_range(start..end, reading);
+            cursor = start + reading.len();
+        } else {
+            tracing::warn!("Kanji '{}' not found in '{}'", base, cform_lemma);
+            return None;
         }
-        return Some(cform_lemma);
     }
-
-    None
+    Some(cform_lemma)
 }
 
 fn process_word_entry(edition: EditionLang, source: Lang, word_entry: &WordEntry) -> LemmaInfo {
     LemmaInfo {
         gloss_tree: get_gloss_tree(word_entry),
+        alt_readings: Vec::new(),
         etymology_text: word_entry
             .etymology_texts()
             .map(|etymology_text| etymology_text.join("\n")),
-        head_info_text: get_head_info(&word_entry.head_templates).map(String::from),
+        etymology_mentions: get_etymology_mentions(word_entry),
+        head_info: match rules().edition(edition).head_info() {
+            HeadInfoStrategy::Forms => get_head_info_from_forms(word_entry),
+            HeadInfoStrategy::Template => None,
+        }
+        .or_else(|| {
+            get_head_info(&word_entry.head_templates)
+                .map(|expansion| HeadInfo::Text(expansion.to_string()))
+        }),
+        linkages: get_linkages(word_entry),
+        notes: word_entry.notes.clone(),
+        term_tags: word_entry
+            .tags
+            .iter()
+            .chain(&word_entry.topics)
+            .cloned()
+            .collect(),
         link_wiktionary: link_wiktionary(edition, source, &word_entry.word),
         link_kaikki: link_kaikki(edition, source, &word_entry.word),
     }
@@ -761,43 +971,180 @@ fn get_head_info(head_templates: &[HeadTemplate]) -> Option<&str> {
     })
 }
 
+/// Return the linked words of every kind, entry level first, without duplicates.
+fn get_linkages(word_entry: &WordEntry) -> Vec<(LinkageKind, Vec<String>)> {
+    let mut linkages = Vec::new();
+
+    for kind in LinkageKind::ALL {
+        let mut words: Vec<String> = Vec::new();
+        let sense_linkages = word_entry.senses.iter().map(|sense| sense.linkages(kind));
+        for linkage in std::iter::once(word_entry.linkages(kind))
+            .chain(sense_linkages)
+            .flatten()
+        {
+            if !linkage.word.is_empty()
+                && linkage.word != word_entry.word
+                && !words.contains(&linkage.word)
+            {
+                words.push(linkage.word.clone());
+            }
+        }
+        if !words.is_empty() {
+            linkages.push((kind, words));
+        }
+    }
+
+    linkages
+}
+
+const GENDER_TAGS: [&str; 4] = ["masculine", "feminine", "neuter", "common"];
+
+/// Tags of head line forms that are not grammar.
+const HEAD_INFO_IGNORED_FORM_TAGS: [&str; 3] = ["alternative", "also", "transliteration"];
+
+/// Return the gender and the head line forms (ex. genitive, plural, principal parts).
+///
+/// The gender is taken from the word tags, or from the tags shared by every sense.
+fn get_head_info_from_forms(word_entry: &WordEntry) -> Option<HeadInfo> {
+    let gender: Vec<Tag> = GENDER_TAGS
+        .iter()
+        .filter(|gender| {
+            word_entry.tags.iter().any(|tag| tag == *gender)
+                || (!word_entry.senses.is_empty()
+                    && word_entry
+                        .senses
+                        .iter()
+                        .all(|sense| sense.tags.iter().any(|tag| tag == *gender)))
+        })
+        .map(|gender| (*gender).to_string())
+        .collect();
+
+    let mut grouped: Map<String, Vec<String>> = Map::default();
+    for form in &word_entry.forms {
+        if !form.source.is_empty()
+            || form.form.is_empty()
+            || form.form.starts_with(['-', '‑'])
+            || form.tags.is_empty()
+            || form.tags.iter().any(|tag| {
+                BLACKLISTED_FORM_TAGS.contains(&tag.as_str())
+                    || HEAD_INFO_IGNORED_FORM_TAGS.contains(&tag.as_str())
+            })
+        {
+            continue;
+        }
+
+        let mut tags: Vec<_> = form.tags.iter().map(String::as_str).collect();
+        sort_tags(&mut tags);
+        let forms = grouped.entry(tags.join(" ")).or_default();
+        if !forms.contains(&form.form) {
+            forms.push(form.form.clone());
+        }
+    }
+
+    if gender.is_empty() && grouped.is_empty() {
+        return None;
+    }
+
+    Some(HeadInfo::Forms {
+        gender,
+        forms: grouped.into_iter().collect(),
+    })
+}
+
+/// Return the words of the same language that the etymology mentions (ex. "fahren + -er").
+///
+/// Affixes are skipped, since they would rarely lead anywhere.
+fn get_etymology_mentions(word_entry: &WordEntry) -> Vec<String> {
+    let mut mentions = Vec::new();
+
+    for template in &word_entry.etymology_templates {
+        if template.args.get("1") != Some(&word_entry.lang_code) {
+            continue;
+        }
+
+        // The positional arguments after the language
+        let parts: Vec<&String> = (2..)
+            .map_while(|position| template.args.get(&position.to_string()))
+            .collect();
+        // Which of them are words, as opposed to affixes written without hyphens
+        let words = match template.name.as_str() {
+            "af" | "affix" | "blend" | "com" | "compound" | "surf" | "univerbation" => &parts[..],
+            "con" | "confix" if parts.len() > 2 => &parts[1..parts.len() - 1],
+            "pre" | "prefix" => &parts[parts.len().saturating_sub(1)..],
+            "suf" | "suffix" | "l" | "link" | "m" | "mention" => &parts[..parts.len().min(1)],
+            _ => continue,
+        };
+
+        for word in words {
+            // Drop inline modifiers: "isä<t:father>"
+            let word = word.split('<').next().unwrap_or_default().trim();
+            if word.is_empty()
+                || word == word_entry.word
+                || word.starts_with(['-', '*'])
+                || word.ends_with('-')
+                || mentions.iter().any(|mention| mention == word)
+            {
+                continue;
+            }
+            mentions.push(word.to_string());
+        }
+    }
+
+    mentions
+}
+
 fn get_gloss_tree(entry: &WordEntry) -> GlossTree {
     let mut gloss_tree = GlossTree::default();
 
     for sense in &entry.senses {
-        // rg: examplefiltering
-        // bunch of example filtering: skip
-
-        let mut filtered_examples: Vec<_> = sense
-            .examples
-            .iter()
-            .filter(|ex| !ex.text.is_empty() && ex.text.chars().count() <= 120) // equal to JS length
-            .cloned()
-            .collect();
-        // Place examples with translations first
-        filtered_examples.sort_by_key(|ex| ex.translation.is_empty());
-
-        insert_glosses(
-            &mut gloss_tree,
-            &sense.glosses,
-            &sense.tags,
-            &sense.topics,
-            &filtered_examples,
-        );
+        // Examples were already filtered in preprocess_main
+        insert_glosses(&mut gloss_tree, &sense.glosses, sense, &sense.examples);
     }
 
     gloss_tree
 }
 
+const DEFAULT_MAX_EXAMPLE_LENGTH: usize = 120;
+
+// rg: examplefiltering
+//
+// Drop empty, long and repeated examples, sort the rest by preference, and keep at most
+// --max-examples of them.
+fn filter_examples(options: &Options, examples: &mut Vec<Example>) {
+    let max_length = options
+        .max_example_length
+        .unwrap_or(DEFAULT_MAX_EXAMPLE_LENGTH);
+    // chars().count() is equal to JS length
+    examples.retain(|ex| !ex.text.is_empty() && ex.text.chars().count() <= max_length);
+
+    // Stable: examples keep their wiktionary order within the same key
+    match options.prefer_examples {
+        ExamplePreference::Translated => examples.sort_by_key(|ex| ex.translation.is_empty()),
+        ExamplePreference::Quotations => {
+            examples.sort_by_key(|ex| (!ex.is_quotation(), ex.translation.is_empty()));
+        }
+        ExamplePreference::Usage => {
+            examples.sort_by_key(|ex| (ex.is_quotation(), ex.translation.is_empty()));
+        }
+    }
+
+    // After sorting, so that we keep the preferred duplicate
+    let mut seen = Set::default();
+    examples.retain(|ex| seen.insert(ex.text.clone()));
+
+    if let Some(max_examples) = options.max_examples {
+        examples.truncate(max_examples);
+    }
+}
+
 /// Recursive helper to deal with nested glosses
 fn insert_glosses(
     gloss_tree: &mut GlossTree,
     glosses: &[String],
-    tags: &[Tag],
-    topics: &[Tag],
+    sense: &Sense,
     examples: &[Example],
 ) {
-    let Some(head) = glosses.get(0) else {
+    let Some(head) = glosses.first() else {
         return;
     };
 
@@ -805,236 +1152,117 @@ fn insert_glosses(
 
     // get or insert node with only tags at this level
     let node = gloss_tree.entry(head.clone()).or_insert_with(|| GlossInfo {
-        tags: tags.to_vec(),
-        topics: topics.to_vec(),
+        tags: sense.tags.clone(),
+        topics: sense.topics.clone(),
         ..Default::default()
     });
 
     // intersect tags if node already exists
     if !node.tags.is_empty() {
-        node.tags = tags
+        node.tags = sense
+            .tags
             .iter()
             .filter(|&t| node.tags.contains(t))
             .cloned()
             .collect();
     }
 
-    // assign examples to the last level
+    // assign examples and qualifiers to the last level
     if tail.is_empty() {
         node.examples = examples.to_vec();
+        node.qualifier.clone_from(&sense.qualifier);
+        node.raw_tags.clone_from(&sense.raw_tags);
         return;
     }
 
-    insert_glosses(&mut node.children, tail, tags, topics, examples);
+    insert_glosses(&mut node.children, tail, sense, examples);
 }
 
-static DE_INFLECTION_RE: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(
-        r"^(.*)des (?:Verbs|Adjektivs|Substantivs|Demonstrativpronomens|Possessivpronomens|Pronomens) (.*)$"
-    ).unwrap()
-});
-
 // rg: isinflectiongloss
-fn is_inflection_sense(edition: EditionLang, sense: &Sense) -> bool {
-    match edition {
-        EditionLang::De => sense
-            .glosses
-            .iter()
-            .any(|gloss| DE_INFLECTION_RE.is_match(gloss)),
-        EditionLang::El => {
-            !sense.form_of.is_empty() && sense.glosses.iter().any(|gloss| gloss.contains("του"))
-        }
-        EditionLang::En => {
-            sense.glosses.iter().any(|gloss| {
-                if gloss.contains("inflection of") {
-                    return true;
-                }
-
-                for form in &sense.form_of {
-                    if form.word.is_empty() {
-                        continue;
-                    }
-                    // We are looking for "... of {word}$" or "... of {word} (text)$"
-                    //
-                    // Cf.
-                    // ... imperative of iki
-                    // ... perfective of возни́кнуть (vozníknutʹ)
-                    // But no
-                    // ... agent noun of fahren; driver (person)
-                    let subs = format!("of {}", form.word);
-                    if gloss.ends_with(&subs)
-                        || (gloss.contains(&format!("{subs} (")) && gloss.ends_with(')'))
-                    {
-                        return true;
-                    }
-                }
-
-                false
-            })
-        }
-        EditionLang::Fr => {
-            !sense.form_of.is_empty()
-                && sense
-                    .glosses
-                    .iter()
-                    .any(|gloss| gloss.contains("personne du"))
-        }
-        _ => false,
-    }
+fn match_inflection_sense(edition: EditionLang, sense: &Sense) -> Option<InflectionMatch> {
+    rules().edition(edition).inflection.as_ref()?.matches(sense)
 }
 
-const TAGS_RETAINED_EL: [&str; 9] = [
-    "masculine",
-    "feminine",
-    "neuter",
-    "singular",
-    "plural",
-    "nominative",
-    "accusative",
-    "genitive",
-    "vocative",
-];
-
+/// Cf. `InflectionRule`.
 fn handle_inflection_sense(
     edition: EditionLang,
     source: Lang,
     word_entry: &WordEntry,
     sense: &Sense,
+    inflection: InflectionMatch,
     irs: &mut Tidy,
 ) {
-    debug_assert!(!sense.glosses.is_empty()); // we checked @ is_inflection_sense
+    let Some(rule) = &rules().edition(edition).inflection else {
+        return;
+    };
 
-    match edition {
-        EditionLang::De => {
-            if let Some(caps) = DE_INFLECTION_RE.captures(&sense.glosses[0])
-                && let (Some(inflection_tags), Some(uninflected)) = (caps.get(1), caps.get(2))
-            {
-                let inflection_tags = inflection_tags.as_str().trim();
-
-                if !inflection_tags.is_empty() {
-                    irs.insert_form(
-                        uninflected.as_str(),
-                        &word_entry.word,
-                        &word_entry.pos,
-                        FormSource::Inflection,
-                        vec![inflection_tags.to_string()],
-                    );
-                }
-            }
-        }
-        EditionLang::El => {
-            let allowed_tags: Vec<_> = sense
-                .tags
-                .iter()
-                .filter(|tag| TAGS_RETAINED_EL.contains(&tag.as_str()))
-                .map(String::from)
-                .collect();
-            let inflection_tags: Vec<_> = if allowed_tags.is_empty() {
-                // very rare
-                vec![format!("redirected from {}", word_entry.word)]
-            } else {
-                allowed_tags
-            };
-            for form in &sense.form_of {
-                irs.insert_form(
-                    &form.word,
-                    &word_entry.word,
-                    &word_entry.pos,
-                    FormSource::Inflection,
-                    // Unfortunate clone. Most sense.form_of only contain one form...
-                    inflection_tags.clone(),
-                );
-            }
-        }
-        EditionLang::En => handle_inflection_sense_en(source, word_entry, sense, irs),
-        EditionLang::Fr => {
-            let allowed_tags: Vec<_> = sense
-                .tags
-                .iter()
-                .filter(|tag| *tag != "form-of")
-                .map(String::from)
-                .collect();
-            let inflection_tags: Vec<_> = if allowed_tags.is_empty() {
-                vec![format!("redirected from {}", word_entry.word)]
-            } else {
-                allowed_tags
-            };
-            for form in &sense.form_of {
-                irs.insert_form(
-                    &form.word,
-                    &word_entry.word,
-                    &word_entry.pos,
-                    FormSource::Inflection,
-                    inflection_tags.clone(),
-                );
-            }
+    let mut inflection_tags: Vec<_> = if rule.sense_tags {
+        rule.filter_sense_tags(&sense.tags).cloned().collect()
+    } else {
+        Vec::new()
+    };
+    if inflection_tags.is_empty() {
+        if let Some(inflection) = inflection.inflection {
+            inflection_tags.push(inflection);
+        } else if rule.fallback {
+            inflection_tags.push(localize_redirected_from(edition, &word_entry.word));
+        } else {
+            return;
         }
-        _ => unreachable!("Unhandled lang that implements is_inflection_sense"),
     }
-}
 
-fn handle_inflection_sense_en(source: Lang, word_entry: &WordEntry, sense: &Sense, irs: &mut Tidy) {
-    let uninflected = match sense.form_of.as_slice() {
-        [alt_form] => &alt_form.word,
-        _ => return,
+    let inflected = if rule.canonical_inflected {
+        get_canonical_word(source, word_entry).unwrap_or_else(|| word_entry.word.clone())
+    } else {
+        word_entry.word.clone()
     };
 
-    // Not sure if this is better (cf. ru-en) over word_entry.word but it is what was done in
-    // the original, so lets not change that for the moment.
-    let inflected =
-        get_canonical_word(source, word_entry).unwrap_or_else(|| word_entry.word.clone());
-
-    if inflected == *uninflected {
-        return;
-    }
-
-    let mut inflections = Set::default();
-    let of_uninflected = format!("of {uninflected}");
-    for gloss in &sense.glosses {
-        let cleaned = gloss
-            .replace("inflection of ", "")
-            .replace(&of_uninflected, "")
-            .replace(&*uninflected, "")
-            .replace(':', "");
-
-        let inflection = PARENS_RE.replace_all(&cleaned, "").trim().to_string();
+    let uninflected: Vec<&str> = match &inflection.lemma {
+        Some(lemma) => vec![lemma],
+        None => sense
+            .form_of
+            .iter()
+            .map(|form| form.word.as_str())
+            .collect(),
+    };
 
-        if !inflection.is_empty() {
-            inflections.insert(inflection);
+    for uninflected in uninflected {
+        if uninflected.is_empty() || uninflected == inflected {
+            continue;
         }
-    }
-
-    for inflection in inflections {
         irs.insert_form(
-            &uninflected,
+            uninflected,
             &inflected,
             &word_entry.pos,
             FormSource::Inflection,
-            vec![inflection],
+            // Unfortunate clone. Most sense.form_of only contain one form...
+            inflection_tags.clone(),
         );
     }
 }
 
 fn normalize_orthography(source: Lang, word: &str) -> String {
-    match source {
-        Lang::Grc | Lang::La | Lang::Ru => {
-            // Normalize to NFD and drop combining accents
-            word.nfd()
-                .filter(|c| !('\u{0300}'..='\u{036F}').contains(c))
-                .collect()
-        }
-        _ => word.to_string(),
+    if rules().common.strips_accents(source) {
+        // Normalize to NFD and drop combining accents
+        word.nfd()
+            .filter(|c| !('\u{0300}'..='\u{036F}').contains(c))
+            .collect()
+    } else {
+        word.to_string()
     }
 }
 
 #[tracing::instrument(skip_all)]
 fn to_yomitan_lemmas(
     edition: EditionLang,
+    target: Lang,
     options: &Options,
     lemma_map: LemmaMap,
     diagnostics: &mut Diagnostics,
 ) -> Vec<YomitanEntry> {
     let mut yomitan_entries = Vec::new();
+    // An alternative reading may be the main reading of another entry, which has its own row
+    let main_keys: Set<LemmaKey> = lemma_map.0.keys().cloned().collect();
 
     for (key, infos) in lemma_map.0 {
         let LemmaKey {
@@ -1043,61 +1271,143 @@ fn to_yomitan_lemmas(
             pos,
         } = key;
 
-        yomitan_entries.extend(infos.into_iter().map(|info| {
-            to_yomitan_lemma(edition, options, &lemma, &reading, &pos, info, diagnostics)
-        }));
+        for mut info in infos {
+            let alt_readings = std::mem::take(&mut info.alt_readings);
+            let term = to_yomitan_lemma(
+                edition,
+                target,
+                options,
+                &lemma,
+                &reading,
+                &pos,
+                info,
+                diagnostics,
+            );
+            // Alternative readings share the definitions and the sequence of the main reading
+            let alt_terms: Vec<_> = alt_readings
+                .iter()
+                .filter(|alt_reading| {
+                    !main_keys.contains(&LemmaKey {
+                        lemma: lemma.clone(),
+                        reading: (*alt_reading).clone(),
+                        pos: pos.clone(),
+                    })
+                })
+                .map(|reading| {
+                    let mut alt_term = term.clone();
+                    alt_term.1 = yomitan_reading(&lemma, reading).to_string();
+                    alt_term
+                })
+                .collect();
+            yomitan_entries.push(YomitanEntry::TermBank(term));
+            yomitan_entries.extend(alt_terms.into_iter().map(YomitanEntry::TermBank));
+        }
     }
 
     yomitan_entries
 }
 
 // TODO: consume info
+#[allow(clippy::too_many_arguments)]
 fn to_yomitan_lemma(
     edition: EditionLang,
+    target: Lang,
     options: &Options,
     lemma: &str,
     reading: &str,
     pos: &Pos, // should be &str
     info: LemmaInfo,
     diagnostics: &mut Diagnostics,
-) -> YomitanEntry {
+) -> TermBank {
     let found_pos = match find_short_pos(pos) {
         Some(short_pos) => short_pos.to_string(),
         None => pos.clone(),
     };
 
-    let yomitan_reading = if *reading == *lemma { "" } else { reading };
-
     let common_short_tags_found =
-        get_found_tags(options, lemma, pos, &info.gloss_tree, diagnostics);
+        get_found_tags(target, options, lemma, pos, &info.gloss_tree, diagnostics);
+    if options.save_temps {
+        collect_raw_tags(target, lemma, &info.gloss_tree, diagnostics);
+    }
+    let term_short_tags_found = get_found_term_tags(
+        target,
+        options,
+        lemma,
+        &info.term_tags,
+        &common_short_tags_found,
+        diagnostics,
+    );
 
     let mut detailed_definition_content = Node::new_array();
 
-    if info.etymology_text.is_some() || info.head_info_text.is_some() {
+    if info.etymology_text.is_some() || info.head_info.is_some() {
         detailed_definition_content.push(structured_preamble(
+            edition,
             info.etymology_text,
-            info.head_info_text,
+            &info.etymology_mentions,
+            info.head_info,
         ));
     }
 
     detailed_definition_content.push(structured_glosses(
         edition,
+        target,
         info.gloss_tree,
         &common_short_tags_found,
+        options.notes,
     ));
 
-    detailed_definition_content.push(structured_backlink(info.link_wiktionary, info.link_kaikki));
+    if options.notes && !info.notes.is_empty() {
+        detailed_definition_content.push(structured_usage_notes(edition, info.notes));
+    }
+
+    if !info.linkages.is_empty() {
+        detailed_definition_content.push(structured_linkages(edition, info.linkages));
+    }
 
-    YomitanEntry::TermBank(TermBank(
+    detailed_definition_content.push(structured_backlink(
+        edition,
+        info.link_wiktionary,
+        info.link_kaikki,
+    ));
+
+    TermBank(
         lemma.to_string(),
-        yomitan_reading.to_string(),
+        yomitan_reading(lemma, reading).to_string(),
         common_short_tags_found.join(" "),
         found_pos,
         vec![DetailedDefinition::structured(detailed_definition_content)],
-    ))
+        options
+            .freq_list
+            .as_ref()
+            .map_or(0, |freq_list| freq_list.score(lemma)),
+        lemma_sequence(lemma, reading, pos),
+        term_short_tags_found.join(" "),
+    )
+}
+
+/// Yomitan expects an empty reading when it is the same as the term.
+fn yomitan_reading<'a>(lemma: &str, reading: &'a str) -> &'a str {
+    if reading == lemma { "" } else { reading }
+}
+
+/// Sequence number shared by all the definitions of a `LemmaKey`, so that yomitan groups them.
+///
+/// Uses FNV-1a (cf. `Fnv1a`) so that it is stable across runs. It is truncated to 53 bits to remain
+/// a safe integer in javascript.
+fn lemma_sequence(lemma: &str, reading: &str, pos: &str) -> i64 {
+    let mut hasher = Fnv1a::default();
+    for field in [lemma, reading, pos] {
+        // Separator, so that ("ab", "c") and ("a", "bc") differ
+        hasher.write(field.as_bytes());
+        hasher.write(&[0]);
+    }
+
+    (hasher.finish() & ((1 << 53) - 1)) as i64
 }
 
 fn get_found_tags(
+    target: Lang,
     options: &Options,
     lemma: &str,
     pos: &Pos,
@@ -1118,7 +1428,7 @@ fn get_found_tags(
 
     // we add pos (at index 0) for this search!
     for tag in std::iter::once(pos.to_string()).chain(common_tags_iter) {
-        match find_tag_in_bank(&tag) {
+        match find_tag_in_bank(&tag, target) {
             None => {
                 // try modified tag: skip
                 if options.save_temps {
@@ -1137,24 +1447,78 @@ fn get_found_tags(
     common_short_tags_found
 }
 
-fn build_details_entry(ty: &str, content: String) -> Node {
+/// Short tags of the word level tags found in the tag bank, skipping those that already are
+/// definition tags.
+fn get_found_term_tags(
+    target: Lang,
+    options: &Options,
+    lemma: &str,
+    term_tags: &[Tag],
+    definition_tags: &[Tag],
+    diagnostics: &mut Diagnostics,
+) -> Vec<Tag> {
+    let mut term_short_tags_found: Vec<Tag> = Vec::new();
+
+    for tag in term_tags {
+        match find_tag_in_bank(tag, target) {
+            None => {
+                if options.save_temps {
+                    diagnostics.increment_rejected_term_tag(tag.clone(), lemma.to_string());
+                }
+            }
+            Some(res) => {
+                if options.save_temps {
+                    diagnostics.increment_accepted_term_tag(tag.clone(), lemma.to_string());
+                }
+                if !definition_tags.contains(&res.short_tag)
+                    && !term_short_tags_found.contains(&res.short_tag)
+                {
+                    term_short_tags_found.push(res.short_tag);
+                }
+            }
+        }
+    }
+
+    term_short_tags_found
+}
+
+/// The id is always in English, so that it can be styled regardless of the edition.
+fn build_details_entry(edition: EditionLang, label: Label, content: Node) -> Node {
+    let id = label.english().replace(' ', "-");
     wrap(
         NTag::Details,
-        &format!("details-entry-{ty}"),
+        &format!("details-entry-{id}"),
         Node::Array(vec![
-            wrap(NTag::Summary, "summary-entry", Node::Text(ty.into())),
-            wrap(NTag::Div, &format!("{ty}-content"), Node::Text(content)),
+            wrap(
+                NTag::Summary,
+                "summary-entry",
+                Node::Text(localize(edition, label).into()),
+            ),
+            wrap(NTag::Div, &format!("{id}-content"), content),
         ]),
     )
 }
 
-fn structured_preamble(etymology_text: Option<String>, head_info_text: Option<String>) -> Node {
+fn structured_preamble(
+    edition: EditionLang,
+    etymology_text: Option<String>,
+    etymology_mentions: &[String],
+    head_info: Option<HeadInfo>,
+) -> Node {
     let mut preamble_content = Node::new_array();
-    if let Some(head_info_text) = head_info_text {
-        preamble_content.push(build_details_entry("Grammar", head_info_text));
+    if let Some(head_info) = head_info {
+        preamble_content.push(build_details_entry(
+            edition,
+            Label::Grammar,
+            structured_head_info(edition, head_info),
+        ));
     }
     if let Some(etymology_text) = etymology_text {
-        preamble_content.push(build_details_entry("Etymology", etymology_text));
+        preamble_content.push(build_details_entry(
+            edition,
+            Label::Etymology,
+            structured_etymology(etymology_text, etymology_mentions),
+        ));
     }
 
     wrap(
@@ -1164,22 +1528,131 @@ fn structured_preamble(etymology_text: Option<String>, head_info_text: Option<St
     )
 }
 
-fn structured_backlink(wlink: String, klink: String) -> Node {
+/// One collapsed section per kind, with a link to every word.
+fn structured_linkages(edition: EditionLang, linkages: Vec<(LinkageKind, Vec<String>)>) -> Node {
+    let mut linkages_content = Node::new_array();
+    for (kind, words) in linkages {
+        let mut content = Node::new_array();
+        for (idx, word) in words.iter().enumerate() {
+            if idx > 0 {
+                content.push(Node::Text(", ".into()));
+            }
+            content.push(search_link(word));
+        }
+        linkages_content.push(build_details_entry(edition, kind.into(), content));
+    }
+
+    wrap(NTag::Div, "linkages", linkages_content)
+}
+
+fn search_link(word: &str) -> Node {
+    Node::Link(Box::new(LinkContent::new(
+        link_yomitan_search(word),
+        word.to_string(),
+    )))
+}
+
+/// Ex. "masculine; genitive Fuchses, plural Füchse", where every form is a link.
+fn structured_head_info(edition: EditionLang, head_info: HeadInfo) -> Node {
+    let or = format!(" {} ", localize(edition, Label::Or));
+
+    let (gender, forms) = match head_info {
+        HeadInfo::Text(text) => return Node::Text(text),
+        HeadInfo::Forms { gender, forms } => (gender, forms),
+    };
+
+    let mut content = Node::new_array();
+    if !gender.is_empty() {
+        content.push(wrap(
+            NTag::Span,
+            "head-info-gender",
+            Node::Text(gender.join(&or)),
+        ));
+        if !forms.is_empty() {
+            content.push(Node::Text("; ".into()));
+        }
+    }
+    for (idx, (tags, forms)) in forms.into_iter().enumerate() {
+        if idx > 0 {
+            content.push(Node::Text(", ".into()));
+        }
+        content.push(wrap(NTag::Span, "head-info-tags", Node::Text(tags)));
+        content.push(Node::Text(" ".into()));
+        for (idx, form) in forms.iter().enumerate() {
+            if idx > 0 {
+                content.push(Node::Text(or.clone()));
+            }
+            content.push(search_link(form));
+        }
+    }
+    content
+}
+
+/// Split the etymology text into text and links to the mentioned words.
+///
+/// Only the first occurrence of every mention, surrounded by non alphanumeric characters, is a
+/// link. Mentions that are not found (ex. when the template displays an alternative form) are
+/// just ignored.
+fn structured_etymology(etymology_text: String, mentions: &[String]) -> Node {
+    let is_boundary = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+
+    let mut spans: Vec<(usize, usize)> = mentions
+        .iter()
+        .filter_map(|mention| {
+            etymology_text
+                .match_indices(mention.as_str())
+                .map(|(start, _)| (start, start + mention.len()))
+                .find(|&(start, end)| {
+                    is_boundary(etymology_text[..start].chars().next_back())
+                        && is_boundary(etymology_text[end..].chars().next())
+                })
+        })
+        .collect();
+    if spans.is_empty() {
+        return Node::Text(etymology_text);
+    }
+    spans.sort_unstable();
+
+    let mut content = Node::new_array();
+    let mut cursor = 0;
+    for (start, end) in spans {
+        if start < cursor {
+            continue; // overlapping mentions
+        }
+        if start > cursor {
+            content.push(Node::Text(etymology_text[cursor..start].to_string()));
+        }
+        content.push(search_link(&etymology_text[start..end]));
+        cursor = end;
+    }
+    if cursor < etymology_text.len() {
+        content.push(Node::Text(etymology_text[cursor..].to_string()));
+    }
+    content
+}
+
+fn structured_backlink(edition: EditionLang, wlink: String, klink: String) -> Node {
     wrap(
         NTag::Div,
         "backlink",
         Node::Array(vec![
-            Node::Backlink(BacklinkContent::new(wlink, "Wiktionary")),
+            Node::Backlink(BacklinkContent::new(
+                wlink,
+                localize(edition, Label::Wiktionary),
+            )),
             Node::Text(" | ".into()), // JMdict uses this separator
             Node::Backlink(BacklinkContent::new(klink, "Kaikki")),
         ]),
     )
 }
 
+/// Qualifiers are only shown with `show_qualifiers`. Cf. `Options::notes`.
 fn structured_glosses(
     edition: EditionLang,
+    target: Lang,
     gloss_tree: GlossTree,
     common_short_tags_found: &[Tag],
+    show_qualifiers: bool,
 ) -> Node {
     wrap(
         NTag::Ol,
@@ -1193,8 +1666,10 @@ fn structured_glosses(
                         "",
                         Node::Array(structured_glosses_go(
                             edition,
+                            target,
                             &GlossTree::from_iter([gloss_pair]),
                             common_short_tags_found,
+                            show_qualifiers,
                             0,
                         )),
                     )
@@ -1204,11 +1679,44 @@ fn structured_glosses(
     )
 }
 
+fn structured_usage_notes(edition: EditionLang, notes: Vec<String>) -> Node {
+    let notes_content = notes
+        .into_iter()
+        .map(|note| wrap(NTag::Div, "usage-note", Node::Text(note)))
+        .collect();
+
+    wrap(
+        NTag::Div,
+        "usage-notes",
+        build_details_entry(edition, Label::UsageNotes, Node::Array(notes_content))
+            .into_array_node(),
+    )
+}
+
+/// Add the raw tags of every gloss that are not in the tag bank to the diagnostics.
+fn collect_raw_tags(
+    target: Lang,
+    lemma: &str,
+    gloss_tree: &GlossTree,
+    diagnostics: &mut Diagnostics,
+) {
+    for gloss_info in gloss_tree.values() {
+        for raw_tag in &gloss_info.raw_tags {
+            if find_tag_in_bank(raw_tag, target).is_none() {
+                diagnostics.increment_raw_tag(raw_tag.clone(), lemma.to_string());
+            }
+        }
+        collect_raw_tags(target, lemma, &gloss_info.children, diagnostics);
+    }
+}
+
 // Recursive helper ~ should return Node for consistency
 fn structured_glosses_go(
     edition: EditionLang,
+    target: Lang,
     gloss_tree: &GlossTree,
     common_short_tags_found: &[Tag],
+    show_qualifiers: bool,
     level: usize,
 ) -> Vec<Node> {
     let html_tag = if level == 0 { NTag::Div } else { NTag::Li };
@@ -1226,10 +1734,16 @@ fn structured_glosses_go(
 
         let mut level_content = Node::new_array();
 
-        if let Some(structured_tags) = structured_tags(&minimal_tags, common_short_tags_found) {
+        if let Some(structured_tags) =
+            structured_tags(target, &minimal_tags, common_short_tags_found)
+        {
             level_content.push(structured_tags);
         }
 
+        if show_qualifiers && let Some(qualifiers) = structured_qualifiers(gloss_info) {
+            level_content.push(qualifiers);
+        }
+
         level_content.push(Node::Text(gloss.into()));
 
         if !gloss_info.examples.is_empty() {
@@ -1251,8 +1765,10 @@ fn structured_glosses_go(
             "",
             Node::Array(structured_glosses_go(
                 edition,
+                target,
                 &gloss_info.children,
                 &new_common_short_tags_found,
+                show_qualifiers,
                 level + 1,
             )),
         ));
@@ -1261,11 +1777,29 @@ fn structured_glosses_go(
     nested
 }
 
-fn structured_tags(tags: &[Tag], common_short_tags_found: &[Tag]) -> Option<Node> {
+/// Ex. "(in various idiomatic expressions) "
+fn structured_qualifiers(gloss_info: &GlossInfo) -> Option<Node> {
+    let qualifiers: Vec<&str> = std::iter::once(gloss_info.qualifier.as_str())
+        .chain(gloss_info.raw_tags.iter().map(String::as_str))
+        .filter(|qualifier| !qualifier.is_empty())
+        .collect();
+
+    if qualifiers.is_empty() {
+        None
+    } else {
+        Some(wrap(
+            NTag::Span,
+            "qualifier",
+            Node::Text(format!("({}) ", qualifiers.join(", "))),
+        ))
+    }
+}
+
+fn structured_tags(target: Lang, tags: &[Tag], common_short_tags_found: &[Tag]) -> Option<Node> {
     let structured_tags_content: Vec<_> = tags
         .iter()
         .filter_map(|tag| {
-            if let Some(tag_info) = find_tag_in_bank(tag)
+            if let Some(tag_info) = find_tag_in_bank(tag, target)
                 && !common_short_tags_found.contains(&tag_info.short_tag)
             {
                 Some(
@@ -1374,7 +1908,334 @@ fn to_yomitan_forms(source: Lang, form_map: FormMap) -> Vec<YomitanEntry> {
                 "non-lemma".into(),
                 String::new(),
                 deinflection_definitions,
+                0,
+                -1,
+                String::new(),
             ))
         })
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::kaikki::{AltForm, Sound};
+
+    fn zh_entry(word: &str, glosses: &[&str], sounds: &[(&str, &[&str])]) -> WordEntry {
+        let mut word_entry = WordEntry::default();
+        word_entry.word = word.to_string();
+        word_entry.pos = "noun".to_string();
+        word_entry.senses = glosses
+            .iter()
+            .map(|gloss| Sense {
+                glosses: vec![gloss.to_string()],
+                ..Default::default()
+            })
+            .collect();
+        word_entry.sounds = sounds
+            .iter()
+            .map(|(zh_pron, tags)| Sound {
+                zh_pron: zh_pron.to_string(),
+                tags: tags.iter().map(ToString::to_string).collect(),
+                ..Default::default()
+            })
+            .collect();
+        word_entry
+    }
+
+    fn xing() -> WordEntry {
+        zh_entry(
+            "行",
+            &["to walk; to go", "row; line"],
+            &[
+                ("xíng (xing²)", &["Mandarin", "Standard", "Pinyin"]),
+                ("háng (hang²)", &["Mandarin", "Standard", "Pinyin"]),
+                (
+                    "xing",
+                    &[
+                        "Mandarin",
+                        "Standard",
+                        "Pinyin",
+                        "toneless-final-syllable-variant",
+                    ],
+                ),
+                (
+                    "dian⁴ nao³",
+                    &["Mandarin", "Chengdu", "Sichuanese", "Pinyin"],
+                ),
+                ("hang⁴", &["Cantonese", "Guangzhou", "Jyutping"]),
+            ],
+        )
+    }
+
+    #[test]
+    fn chinese_readings() {
+        assert_eq!(get_chinese_readings(Lang::Zh, &xing()), ["xíng", "háng"]);
+
+        let sik = zh_entry(
+            "食",
+            &["to eat"],
+            &[
+                ("sik⁶", &["Cantonese", "Guangzhou", "Jyutping"]),
+                ("sihk", &["Cantonese", "Guangzhou", "Yale", "Jyutping"]),
+                ("zi⁶", &["Cantonese", "Guangzhou", "Jyutping"]),
+            ],
+        );
+        assert_eq!(get_chinese_readings(Lang::Yue, &sik), ["sik⁶", "zi⁶"]);
+    }
+
+    #[test]
+    fn chinese_readings_share_every_sense() {
+        let mut irs = Tidy::default();
+        process_main(EditionLang::En, Lang::Zh, &xing(), &mut irs);
+        let terms = to_yomitan_lemmas(
+            EditionLang::En,
+            Lang::En,
+            &Options::default(),
+            irs.lemma_map,
+            &mut Diagnostics::default(),
+        );
+
+        let readings: Vec<_> = terms
+            .iter()
+            .map(|term| {
+                let YomitanEntry::TermBank(term) = term else {
+                    panic!("lemmas are terms");
+                };
+                let definitions = serde_json::to_string(&term.4).unwrap();
+                assert!(definitions.contains("to walk; to go"), "{definitions}");
+                assert!(definitions.contains("row; line"), "{definitions}");
+                term.1.as_str()
+            })
+            .collect();
+        assert_eq!(readings, ["xíng", "háng"]);
+    }
+
+    #[test]
+    fn chinese_variants() {
+        let mut traditional = zh_entry("電腦", &["computer"], &[]);
+        traditional.forms = vec![Form {
+            form: "电脑".to_string(),
+            tags: vec!["Simplified-Chinese".to_string()],
+            ..Default::default()
+        }];
+        let mut simplified = zh_entry("电脑", &[], &[]);
+        simplified.senses = vec![Sense {
+            tags: vec!["no-gloss".to_string()],
+            ..Default::default()
+        }];
+        simplified.forms = vec![Form {
+            form: "電腦".to_string(),
+            tags: vec!["Traditional-Chinese".to_string()],
+            ..Default::default()
+        }];
+
+        // Both redirect 电脑 to the entry with the glosses
+        for word_entry in [traditional, simplified] {
+            let mut irs = Tidy::default();
+            process_chinese_variants(&word_entry, &mut irs);
+            let forms: Vec<_> = irs
+                .form_map
+                .0
+                .into_iter()
+                .map(|(key, (_, tags))| (key.uninflected, key.inflected, tags))
+                .collect();
+            assert_eq!(
+                forms,
+                [(
+                    "電腦".to_string(),
+                    "电脑".to_string(),
+                    vec!["Simplified-Chinese".to_string()]
+                )]
+            );
+        }
+    }
+
+    fn inflection_forms(
+        edition: EditionLang,
+        source: Lang,
+        word: &str,
+        gloss: &str,
+        form_of: &str,
+        tags: &[&str],
+    ) -> Vec<(String, String, Vec<String>)> {
+        let mut word_entry = WordEntry::default();
+        word_entry.word = word.to_string();
+        word_entry.pos = "noun".to_string();
+        let sense = Sense {
+            glosses: vec![gloss.to_string()],
+            form_of: vec![AltForm {
+                word: form_of.to_string(),
+            }],
+            tags: tags.iter().map(ToString::to_string).collect(),
+            ..Default::default()
+        };
+
+        let Some(inflection) = match_inflection_sense(edition, &sense) else {
+            return Vec::new();
+        };
+        let mut irs = Tidy::default();
+        handle_inflection_sense(edition, source, &word_entry, &sense, inflection, &mut irs);
+        irs.form_map
+            .0
+            .into_iter()
+            .map(|(key, (_, tags))| (key.uninflected, key.inflected, tags))
+            .collect()
+    }
+
+    #[test]
+    fn example_filtering() {
+        let example = |text: &str, translation: &str, reference: &str| Example {
+            text: text.to_string(),
+            translation: translation.to_string(),
+            reference: reference.to_string(),
+        };
+        let examples = vec![
+            example("usage", "", ""),
+            example("quotation", "", "Goethe"),
+            example("translated usage", "a translation", ""),
+            example("translated quotation", "a translation", "Kafka"),
+            example("", "", ""),
+            example("quotation", "a translation", "Mann"),
+            example("usage", "", "Brecht"),
+        ];
+        let filtered = |options: &Options| {
+            let mut examples = examples.clone();
+            filter_examples(options, &mut examples);
+            examples
+                .into_iter()
+                .map(|ex| format!("{} {}", ex.text, ex.reference))
+                .collect::<Vec<_>>()
+        };
+
+        // Duplicates keep the preferred example
+        let mut options = Options::default();
+        assert_eq!(
+            filtered(&options),
+            [
+                "translated usage ",
+                "translated quotation Kafka",
+                "quotation Mann",
+                "usage ",
+            ]
+        );
+
+        options.prefer_examples = ExamplePreference::Quotations;
+        assert_eq!(
+            filtered(&options),
+            [
+                "translated quotation Kafka",
+                "quotation Mann",
+                "usage Brecht",
+                "translated usage ",
+            ]
+        );
+
+        options.prefer_examples = ExamplePreference::Usage;
+        assert_eq!(
+            filtered(&options),
+            [
+                "translated usage ",
+                "usage ",
+                "translated quotation Kafka",
+                "quotation Mann",
+            ]
+        );
+
+        options.max_examples = Some(1);
+        assert_eq!(filtered(&options), ["translated usage "]);
+
+        options.max_examples = None;
+        options.max_example_length = Some(5);
+        assert_eq!(filtered(&options), ["usage "]);
+    }
+
+    #[test]
+    fn lemmas_merge_on_the_main_reading() {
+        let mut word_entry = WordEntry::default();
+        word_entry.word = "五色".to_string();
+        word_entry.senses = vec![Sense {
+            glosses: vec!["five colors".to_string()],
+            ..Default::default()
+        }];
+
+        let mut irs = Tidy::default();
+        for alt_readings in [vec!["ごしょく"], vec![]] {
+            let mut info = process_word_entry(EditionLang::En, Lang::Ja, &word_entry);
+            info.alt_readings = alt_readings.into_iter().map(String::from).collect();
+            irs.insert_lemma("五色", "ごしき".to_string(), "noun", info);
+        }
+
+        assert_eq!(irs.lemma_map.0.len(), 1);
+        assert_eq!(irs.lemma_map.len(), 2);
+
+        // An alternative reading that is the main reading of another entry is not repeated
+        let info = process_word_entry(EditionLang::En, Lang::Ja, &word_entry);
+        irs.insert_lemma("五色", "ごしょく".to_string(), "noun", info);
+        let terms = to_yomitan_lemmas(
+            EditionLang::En,
+            Lang::En,
+            &Options::default(),
+            irs.lemma_map,
+            &mut Diagnostics::default(),
+        );
+        let readings: Vec<_> = terms
+            .iter()
+            .map(|term| match term {
+                YomitanEntry::TermBank(term) => term.1.as_str(),
+                YomitanEntry::TermBankMeta(_) => panic!("lemmas are terms"),
+            })
+            .collect();
+        assert_eq!(readings, ["ごしき", "ごしき", "ごしょく"]);
+    }
+
+    #[test]
+    fn inflection_senses() {
+        use EditionLang as E;
+
+        #[rustfmt::skip]
+        let cases = [
+            (E::Cs, Lang::Cs, "hradu", "genitiv jednotného čísla slova hrad", "hrad", &["form-of"][..], &["genitiv jednotného čísla"][..]),
+            (E::En, Lang::Ru, "снега", "genitive singular of снег (sneg)", "снег", &["form-of"], &["genitive singular"][..]),
+            (E::Es, Lang::Es, "hablo", "Primera persona del singular (yo) del presente de indicativo de hablar.", "hablar", &["form-of", "first-person", "singular", "present", "indicative"], &["first-person", "singular", "present", "indicative"][..]),
+            (E::Id, Lang::Id, "buku-buku", "bentuk jamak dari buku", "buku", &["form-of"], &["bentuk jamak"][..]),
+            (E::It, Lang::It, "case", "plurale di casa", "casa", &["form-of"], &["plurale"][..]),
+            (E::Ja, Lang::Ja, "食べ", "「食べる」の連用形。", "食べる", &["form-of"], &["連用形"][..]),
+            (E::Ko, Lang::Ko, "가요", "가다의 활용형.", "가다", &["form-of"], &["활용형"][..]),
+            (E::Ku, Lang::Ku, "pirtûkan", "forma pirhejmar ya tewandî ya pirtûk", "pirtûk", &["form-of", "plural", "oblique"], &["plural", "oblique"][..]),
+            (E::Ms, Lang::Ms, "kucing-kucing", "bentuk jamak bagi kucing", "kucing", &["form-of"], &["bentuk jamak"][..]),
+            (E::Nl, Lang::Nl, "liep", "enkelvoud verleden tijd van lopen", "lopen", &["form-of"], &["enkelvoud verleden tijd"][..]),
+            (E::Pl, Lang::Pl, "domu", "dopełniacz liczby pojedynczej od dom", "dom", &["form-of"], &["dopełniacz liczby pojedynczej"][..]),
+            (E::Pt, Lang::Pt, "casas", "plural de casa", "casa", &["form-of", "plural"], &["plural"][..]),
+            (E::Ru, Lang::Ru, "снега", "форма родительного падежа единственного числа от снег", "снег", &["form-of"], &["форма родительного падежа единственного числа"][..]),
+            (E::Simple, Lang::Simple, "cats", "The plural form of cat.", "cat", &["form-of"], &["plural"][..]),
+            (E::Th, Lang::En, "cats", "รูปพหูพจน์ของ cat", "cat", &["form-of"], &["รูปพหูพจน์"][..]),
+            (E::Tr, Lang::Tr, "evler", "ev sözcüğünün çoğulu", "ev", &["form-of"], &["çoğulu"][..]),
+            (E::Vi, Lang::En, "cats", "Số nhiều của cat.", "cat", &["form-of"], &["Số nhiều"][..]),
+            (E::Zh, Lang::En, "cats", "cat的複數", "cat", &["form-of"], &["複數"][..]),
+        ];
+
+        for (edition, source, word, gloss, form_of, tags, expected) in cases {
+            assert_eq!(
+                inflection_forms(edition, source, word, gloss, form_of, tags),
+                [(
+                    form_of.to_string(),
+                    word.to_string(),
+                    expected.iter().map(ToString::to_string).collect()
+                )],
+                "{edition:?}: {gloss}"
+            );
+        }
+
+        // Not an inflection: the lemma is not at the end of the gloss
+        let forms = inflection_forms(
+            E::En,
+            Lang::De,
+            "Fahrer",
+            "agent noun of fahren; driver (person)",
+            "fahren",
+            &[],
+        );
+        assert!(forms.is_empty());
+    }
+}
@@ -1,90 +1,161 @@
+mod batch;
+mod builder;
+mod cache;
 mod index;
 mod locale;
 mod main;
 mod other;
+mod progress;
+mod rules;
 
+pub use batch::*;
+pub use builder::*;
 pub use index::*;
 pub use main::*;
 pub use other::*;
+pub use progress::*;
+pub use rules::*;
 
 use anyhow::{Context, Ok, Result};
+use flate2::read::MultiGzDecoder;
 use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 #[allow(unused)]
 use tracing::{Level, debug, error, info, span, trace, warn};
 use zip::ZipWriter;
 use zip::write::SimpleFileOptions;
 
+use std::cell::OnceCell;
 use std::fs::{self, File};
-use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 
 use crate::cli::Options;
 use crate::diagnostic::Diagnostics;
+use crate::dict::cache::read_jsonl_incremental;
 #[cfg(feature = "html")]
 use crate::download::download_jsonl;
+use crate::filter::matches_all;
 use crate::lang::{EditionLang, Lang};
-use crate::models::kaikki::WordEntry;
+use crate::models::kaikki::{LinkageKind, WordEntry};
 use crate::models::yomitan::YomitanEntry;
-use crate::path::PathManager;
+use crate::path::{DictionaryType, PathManager};
 use crate::tags::get_tag_bank_as_tag_info;
-use crate::utils::{
-    CHECK_C, pretty_print_at_path, pretty_println_at_path, skip_because_file_exists,
-};
+use crate::utils::{pretty_print_at_path, skip_because_file_exists};
+use crate::validate::validate_path;
 
-const CONSOLE_PRINT_INTERVAL: i32 = 10000;
 const BANK_SIZE: usize = 25_000;
+/// Extensions of the jsonlines that are decompressed on the fly, in order of preference.
+const COMPRESSED_EXTENSIONS: [&str; 2] = ["gz", "zst"];
+/// Number of lines handed to each worker per batch.
+const LINES_PER_JOB: usize = 4096;
+/// Number of schema violations shown after writing a dictionary. `kty validate` shows them all.
+const MAX_REPORTED_VIOLATIONS: usize = 10;
 
 const STYLES_CSS: &[u8] = include_bytes!("../../assets/styles.css");
 const STYLES_CSS_EXPERIMENTAL: &[u8] = include_bytes!("../../assets/styles_experimental.css");
 
 type LabelledYomitanEntry = (&'static str, Vec<YomitanEntry>);
 
-enum Sink<'a> {
-    Disk,
-    Zip(&'a mut ZipWriter<File>, SimpleFileOptions),
+enum Sink<'a, W: Write + Seek> {
+    /// Directory where to write the banks.
+    Disk(&'a Path),
+    /// The path of the zip, if any, is only used for reporting.
+    Zip(&'a mut ZipWriter<W>, SimpleFileOptions, Option<&'a Path>),
 }
 
 /// Write lemma / form / whatever banks to either disk or zip.
 ///
 /// If `save_temps` is true, we assume that the user is debugging and does not need the zip.
 fn write_yomitan(
-    source: Lang,
-    target: Lang,
     options: &Options,
     pm: &PathManager,
     labelled_entries: &[LabelledYomitanEntry],
+    progress: &mut dyn Progress,
 ) -> Result<()> {
-    let mut bank_index = 0;
-
     if options.save_temps {
         let out_dir = pm.dir_temp_dict();
         fs::create_dir_all(&out_dir)?;
+        // The index makes the directory a valid (unzipped) dictionary, cf. `validate_dir`
+        fs::write(out_dir.join("index.json"), get_index(pm))?;
+        let mut bank_index = 0;
         for (entry_ty, entries) in labelled_entries {
-            write_banks(
+            write_banks::<File>(
                 options.pretty,
-                options.quiet,
                 entries,
                 &mut bank_index,
                 entry_ty,
-                &out_dir,
-                Sink::Disk,
+                Sink::Disk(&out_dir),
+                progress,
             )?;
         }
 
-        if !options.quiet {
-            pretty_println_at_path(&format!("{CHECK_C} Wrote temp data"), &out_dir);
-        }
-        return Ok(());
+        progress.report(ProgressEvent::Written {
+            what: "temp data",
+            path: Some(&out_dir),
+        });
+        return check_schemas(options, &out_dir);
     }
 
     let writer_path = pm.path_dict();
     let writer_file = File::create(&writer_path)?;
-    let mut zip = ZipWriter::new(writer_file);
+    write_zip(
+        writer_file,
+        options,
+        pm,
+        labelled_entries,
+        Some(&writer_path),
+        progress,
+    )?;
+
+    progress.report(ProgressEvent::Written {
+        what: "yomitan dict",
+        path: Some(&writer_path),
+    });
+
+    check_schemas(options, &writer_path)
+}
+
+/// Warn about the parts of the written dictionary that do not conform to the yomitan schemas,
+/// since yomitan would refuse to import it.
+fn check_schemas(options: &Options, path: &Path) -> Result<()> {
+    if options.skip_validation {
+        return Ok(());
+    }
+
+    let violations = validate_path(path)?;
+    for violation in violations.iter().take(MAX_REPORTED_VIOLATIONS) {
+        warn!("{violation}");
+    }
+    if !violations.is_empty() {
+        warn!(
+            "{} does not conform to the yomitan schemas ({} violations, run `kty validate` to see them all)",
+            path.display(),
+            violations.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Write the yomitan dictionary as a zip to `writer`, and return the writer.
+///
+/// `writer_path` is only used for reporting.
+fn write_zip<W: Write + Seek>(
+    writer: W,
+    options: &Options,
+    pm: &PathManager,
+    labelled_entries: &[LabelledYomitanEntry],
+    writer_path: Option<&Path>,
+    progress: &mut dyn Progress,
+) -> Result<W> {
+    let mut zip = ZipWriter::new(writer);
     let zip_options =
         SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
 
     // Zip index.json
-    let index_string = get_index(&pm.dict_name_expanded(), source, target);
+    let index_string = get_index(pm);
     zip.start_file("index.json", zip_options)?;
     zip.write_all(index_string.as_bytes())?;
 
@@ -97,42 +168,36 @@ fn write_yomitan(
     }
 
     // Copy paste tag_bank.json
-    let tag_bank = get_tag_bank_as_tag_info();
+    let (_, _, target) = pm.langs();
+    let tag_bank = get_tag_bank_as_tag_info(target);
     let tag_bank_bytes = serde_json::to_vec_pretty(&tag_bank)?;
     zip.start_file("tag_bank_1.json", zip_options)?; // it needs to end in _1
     zip.write_all(&tag_bank_bytes)?;
 
+    let mut bank_index = 0;
     for (entry_ty, entries) in labelled_entries {
         write_banks(
             options.pretty,
-            options.quiet,
             entries,
             &mut bank_index,
             entry_ty,
-            &writer_path,
-            Sink::Zip(&mut zip, zip_options),
+            Sink::Zip(&mut zip, zip_options, writer_path),
+            progress,
         )?;
     }
 
-    zip.finish()?;
-
-    if !options.quiet {
-        pretty_println_at_path(&format!("{CHECK_C} Wrote yomitan dict"), &writer_path);
-    }
-
-    Ok(())
+    Ok(zip.finish()?)
 }
 
 /// Writes `yomitan_entries` in batches to `out_sink` (either disk or a zip).
 #[tracing::instrument(skip_all)]
-fn write_banks(
+fn write_banks<W: Write + Seek>(
     pretty: bool,
-    quiet: bool,
     yomitan_entries: &[YomitanEntry],
     bank_index: &mut usize,
     entry_ty: &str,
-    out_dir: &Path,
-    mut sink: Sink,
+    mut sink: Sink<W>,
+    progress: &mut dyn Progress,
 ) -> Result<()> {
     // NOTE: this assumes that once a type is passed, all the remaining entries are of same type
     let bank_name_prefix = match yomitan_entries.first() {
@@ -152,37 +217,28 @@ fn write_banks(
         };
 
         let bank_name = format!("{bank_name_prefix}_{bank_index}.json");
-        let file_path = out_dir.join(&bank_name);
 
-        match sink {
-            Sink::Disk => {
+        let file_path = match sink {
+            Sink::Disk(out_dir) => {
+                let file_path = out_dir.join(&bank_name);
                 let mut file = File::create(&file_path)?;
                 file.write_all(&json_bytes)?;
+                Some(file_path)
             }
-            Sink::Zip(ref mut zip, zip_options) => {
+            Sink::Zip(ref mut zip, zip_options, writer_path) => {
                 zip.start_file(&bank_name, zip_options)?;
                 zip.write_all(&json_bytes)?;
+                writer_path.map(|writer_path| writer_path.join(&bank_name))
             }
-        }
-
-        if !quiet {
-            if bank_num > 0 {
-                print!("\r\x1b[K");
-            }
-            pretty_print_at_path(
-                &format!(
-                    "Wrote yomitan {entry_ty} bank {}/{total_bank_num} ({} entries)",
-                    bank_num + 1,
-                    bank.len()
-                ),
-                &file_path,
-            );
-            std::io::stdout().flush()?;
-        }
-    }
+        };
 
-    if !quiet {
-        println!();
+        progress.report(ProgressEvent::Bank {
+            label: entry_ty,
+            num: bank_num + 1,
+            total: total_bank_num,
+            entries: bank.len(),
+            path: file_path.as_deref(),
+        });
     }
 
     Ok(())
@@ -191,28 +247,52 @@ fn write_banks(
 /// Trait for Intermediate representation. Used for postprocessing (merge, etc.) and debugging via snapshots.
 ///
 /// The simplest form is a Vec<YomitanEntry> if we don't want to do anything fancy, cf. `DGlossary`
-pub trait Intermediate: Default {
+pub trait Intermediate: Default + Send {
     fn len(&self) -> usize;
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
+    /// Append `other` to `self`, as if the entries of `other` had been processed after ours.
+    ///
+    /// Workers build their own `Self` from contiguous chunks of the input, which are then merged
+    /// back in order. The result must be identical to a sequential run.
+    fn merge(&mut self, other: Self);
+
     /// How to write `Self::I` to disk. This is only called if `options.save_temps` is set and
     /// `Dictionary::write_ir` returns true.
     #[allow(unused_variables)]
     fn write(&self, pm: &PathManager, options: &Options) -> Result<()> {
         Ok(())
     }
+
+    /// Serialize `self` for the `--incremental` cache, as a single line of json.
+    fn to_cache(&self) -> Result<String>;
+
+    /// Inverse of `to_cache`.
+    fn from_cache(cache: &str) -> Result<Self>;
 }
 
 impl<T> Intermediate for Vec<T>
 where
-    T: Serialize,
+    T: Serialize + DeserializeOwned + Send,
 {
     fn len(&self) -> usize {
         Self::len(self)
     }
 
+    fn merge(&mut self, other: Self) {
+        self.extend(other);
+    }
+
+    fn to_cache(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    fn from_cache(cache: &str) -> Result<Self> {
+        Ok(serde_json::from_str(cache)?)
+    }
+
     fn write(&self, pm: &PathManager, options: &Options) -> Result<()> {
         let writer_path = pm.dir_tidy().join("tidy.jsonl");
         let writer_file = File::create(&writer_path)?;
@@ -230,9 +310,18 @@ where
 }
 
 /// Trait to abstract the process of making a dictionary.
-pub trait Dictionary {
+///
+/// It is shared between the workers of `make_dict`, hence the `Sync` bound.
+pub trait Dictionary: Sync {
     type I: Intermediate;
 
+    /// Used for paths and the index. Cf. `PathManager`.
+    const DICT_TYPE: DictionaryType;
+
+    /// Whether `preprocess` mutates the entries. Otherwise, a batch can share them between its
+    /// dictionaries instead of cloning them (cf. `BatchItem::feed`).
+    const MUTATES_ENTRIES: bool = false;
+
     // NOTE:Maybe in the future we can get rid of this. It requires cleaning up the legacy mutable
     // behaviour of the main dictionary.
     //
@@ -261,9 +350,10 @@ pub trait Dictionary {
         irs: &mut Self::I,
     );
 
-    /// Console message for found entries. It is customized for the main dictionary.
-    fn found_ir_message(&self, irs: &Self::I) {
-        println!("Found {} entries", irs.len());
+    /// Summary of found entries, reported as `ProgressEvent::Found`. It is customized for the
+    /// main dictionary.
+    fn found_ir_message(&self, irs: &Self::I) -> String {
+        format!("Found {} entries", irs.len())
     }
 
     /// Whether to write or not `Self::I` to disk.
@@ -302,28 +392,80 @@ pub trait Dictionary {
     }
 }
 
-fn find_or_download_jsonl(
+/// Return the first existing path, looking for compressed variants of every path in `paths`.
+///
+/// If there is none (or we redownload), download to the last path of `paths`, keeping the
+/// compressed download if the `keep_compressed` option is set.
+pub fn find_or_download_jsonl(
     edition: EditionLang,
     lang: Lang,
     paths: &[PathBuf],
     options: &Options,
 ) -> Result<PathBuf> {
-    let first_path_found = paths.iter().find(|pbuf| pbuf.exists());
+    let first_path_found = paths
+        .iter()
+        .flat_map(|pbuf| {
+            std::iter::once(pbuf.clone()).chain(
+                COMPRESSED_EXTENSIONS
+                    .iter()
+                    .map(|ext| pbuf.with_added_extension(ext)),
+            )
+        })
+        .find(|pbuf| pbuf.exists());
 
     if let (false, Some(pbuf)) = (options.redownload, first_path_found) {
         if !options.quiet {
-            skip_because_file_exists("download", pbuf);
+            skip_because_file_exists("download", &pbuf);
         }
-        Ok(pbuf.clone())
+        Ok(pbuf)
     } else {
-        let path_jsonl_of_download = paths.last().unwrap();
+        let mut path_jsonl_of_download = paths.last().unwrap().clone();
+        if options.keep_compressed {
+            path_jsonl_of_download = path_jsonl_of_download.with_added_extension("gz");
+        }
         #[cfg(feature = "html")]
-        download_jsonl(edition, lang, path_jsonl_of_download, options.quiet)?;
-        Ok(path_jsonl_of_download.clone())
+        download_jsonl(edition, lang, &path_jsonl_of_download, options.quiet)?;
+        Ok(path_jsonl_of_download)
     }
 }
 
+/// Open a jsonlines, decompressing it on the fly if it has a compressed extension.
+///
+/// The path `-` stands for stdin.
+fn open_jsonl(path_jsonl: &Path) -> Result<Box<dyn BufRead>> {
+    let capacity = 256 * (1 << 10); // default is 8 * (1 << 10) := 8KB
+
+    if path_jsonl == Path::new("-") {
+        let stdin = std::io::stdin().lock();
+        return Ok(Box::new(BufReader::with_capacity(capacity, stdin)));
+    }
+
+    let file = File::open(path_jsonl)
+        .with_context(|| format!("Failed to open {}", path_jsonl.display()))?;
+
+    let reader: Box<dyn Read> = match path_jsonl.extension().and_then(|ext| ext.to_str()) {
+        // Multi because kaikki files may be made of several concatenated gzip members
+        Some("gz") => Box::new(MultiGzDecoder::new(BufReader::new(file))),
+        #[cfg(feature = "zstd")]
+        Some("zst") => Box::new(zstd::Decoder::new(file)?),
+        #[cfg(not(feature = "zstd"))]
+        Some("zst") => anyhow::bail!(
+            "Can not read {}: kty was built without the zstd feature",
+            path_jsonl.display()
+        ),
+        _ => Box::new(file),
+    };
+
+    Ok(Box::new(BufReader::with_capacity(capacity, reader)))
+}
+
 fn rejected(entry: &WordEntry, options: &Options) -> bool {
+    rejected_json(entry, &OnceCell::new(), options)
+}
+
+/// Same as `rejected`, for an entry checked against several options: `json` keeps the entry
+/// serialized for the `--where` expressions of all of them (cf. `matches_all`).
+fn rejected_json(entry: &WordEntry, json: &OnceCell<Value>, options: &Options) -> bool {
     options
         .reject
         .iter()
@@ -332,72 +474,287 @@ fn rejected(entry: &WordEntry, options: &Options) -> bool {
             .filter
             .iter()
             .all(|(k, v)| k.field_value(entry) == v)
+        || !matches_all(&options.where_exprs, entry, json)
 }
 
-pub fn make_dict<D: Dictionary>(dict: D, options: &Options, pm: &PathManager) -> Result<()> {
-    let (edition_pm, source_pm, target_pm) = pm.langs();
+/// Read up to `n` lines into `lines`, reusing their allocations.
+///
+/// Return the number of lines read, which is less than `n` only at EOF.
+fn read_lines(reader: &mut impl BufRead, lines: &mut Vec<Vec<u8>>, n: usize) -> Result<usize> {
+    let mut n_read = 0;
 
-    pm.setup_dirs()?;
+    while n_read < n {
+        if n_read == lines.len() {
+            lines.push(Vec::with_capacity(1 << 10));
+        }
+        let line = &mut lines[n_read];
+        line.clear();
+        if reader.read_until(b'\n', line)? == 0 {
+            break; // EOF
+        }
+        n_read += 1;
+    }
 
-    let capacity = 256 * (1 << 10); // default is 8 * (1 << 10) := 8KB
-    let mut line = Vec::with_capacity(1 << 10);
-    let mut entries = D::I::default();
+    Ok(n_read)
+}
 
-    for (edition, paths) in pm.paths_jsonl() {
-        let path_jsonl = find_or_download_jsonl(edition, source_pm, &paths, options)?;
-        tracing::debug!("path_jsonl: {}", path_jsonl.display());
+/// Split `items` into at most `jobs` contiguous chunks and map them in parallel.
+///
+/// The results are returned in the order of the chunks.
+fn par_map_chunks<T, R, F>(items: &mut [T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(&mut [T]) -> R + Sync,
+{
+    if items.is_empty() {
+        return Vec::new();
+    }
 
-        let reader_file = File::open(&path_jsonl)?;
-        let mut reader = BufReader::with_capacity(capacity, reader_file);
+    let chunk_size = items.len().div_ceil(jobs.max(1));
+    if chunk_size == items.len() {
+        return vec![f(items)];
+    }
 
-        let mut line_count = 0;
-        let mut accepted_count = 0;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks_mut(chunk_size)
+            .map(|chunk| scope.spawn(|| f(chunk)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|err| std::panic::resume_unwind(err))
+            })
+            .collect()
+    })
+}
 
-        loop {
-            line.clear();
-            if reader.read_until(b'\n', &mut line)? == 0 {
-                break; // EOF
+/// Deserialize `lines` in parallel, keeping only the entries for which `keep` returns something,
+/// next to what it returned.
+///
+/// Only the given `linkages` are deserialized, cf. `WordEntry::from_json`.
+fn parse_lines<T, F>(
+    lines: &mut [Vec<u8>],
+    jobs: usize,
+    linkages: &[LinkageKind],
+    keep: F,
+) -> Result<Vec<(WordEntry, T)>>
+where
+    T: Send,
+    F: Fn(&WordEntry) -> Option<T> + Sync,
+{
+    let parsed = par_map_chunks(lines, jobs, |chunk| {
+        let mut word_entries = Vec::with_capacity(chunk.len());
+        for line in chunk.iter() {
+            let word_entry = WordEntry::from_json(line, linkages)
+                .with_context(|| "Error decoding JSON @ make_dict")?;
+            if let Some(kept) = keep(&word_entry) {
+                word_entries.push((word_entry, kept));
             }
+        }
+        Ok(word_entries)
+    });
 
-            line_count += 1;
+    let mut word_entries = Vec::new();
+    for chunk in parsed {
+        word_entries.extend(chunk?);
+    }
+    Ok(word_entries)
+}
 
-            let mut word_entry: WordEntry =
-                serde_json::from_slice(&line).with_context(|| "Error decoding JSON @ make_dict")?;
+/// Apply the `first` option to a batch of accepted entries (`WordEntry`, or their `Intermediate`
+/// with `--incremental`).
+///
+/// Entries are counted from `accepted_count`, and we stop *before* the first-th entry. Return true
+/// if the limit was reached, in which case no more lines should be read.
+fn take_first<T>(first: i32, accepted_count: &mut usize, entries: &mut Vec<T>) -> bool {
+    if let Some(first) = usize::try_from(first).ok()
+        && first > *accepted_count
+        && first - *accepted_count <= entries.len()
+    {
+        entries.truncate(first - *accepted_count - 1);
+        *accepted_count = first;
+        true
+    } else {
+        *accepted_count += entries.len();
+        false
+    }
+}
 
-            if !options.quiet && line_count % CONSOLE_PRINT_INTERVAL == 0 {
-                print!("Processed {line_count} lines...\r");
-                std::io::stdout().flush()?;
-            }
+/// Preprocess and process `word_entries` in parallel, merging the results into `entries`.
+fn process_word_entries<D: Dictionary>(
+    dict: &D,
+    edition: EditionLang,
+    source: Lang,
+    target: Lang,
+    options: &Options,
+    word_entries: &mut [WordEntry],
+    entries: &mut D::I,
+) {
+    let irs_chunks = par_map_chunks(word_entries, options.worker_count(), |chunk| {
+        let mut irs = D::I::default();
+        for word_entry in chunk {
+            dict.preprocess(edition, source, target, word_entry, options, &mut irs);
+            dict.process(edition, source, target, word_entry, &mut irs);
+        }
+        irs
+    });
 
-            if rejected(&word_entry, options) {
-                continue;
-            }
+    for irs in irs_chunks {
+        entries.merge(irs);
+    }
+}
 
-            accepted_count += 1;
-            if accepted_count == options.first {
-                break;
-            }
+/// Same as `process_word_entries`, for a dictionary that does not mutate the entries.
+fn process_borrowed_word_entries<D: Dictionary>(
+    dict: &D,
+    edition: EditionLang,
+    source: Lang,
+    target: Lang,
+    options: &Options,
+    word_entries: &mut [&WordEntry],
+    entries: &mut D::I,
+) {
+    debug_assert!(!D::MUTATES_ENTRIES);
+    let irs_chunks = par_map_chunks(word_entries, options.worker_count(), |chunk| {
+        let mut irs = D::I::default();
+        for word_entry in chunk {
+            dict.process(edition, source, target, word_entry, &mut irs);
+        }
+        irs
+    });
 
-            dict.preprocess(
-                edition,
-                source_pm,
-                target_pm,
-                &mut word_entry,
-                options,
-                &mut entries,
-            );
+    for irs in irs_chunks {
+        entries.merge(irs);
+    }
+}
 
-            dict.process(edition, source_pm, target_pm, &word_entry, &mut entries);
+/// The progress reporter of the CLI.
+fn console_progress(options: &Options) -> Box<dyn Progress> {
+    if options.quiet {
+        Box::new(NoProgress)
+    } else {
+        Box::new(ConsoleProgress)
+    }
+}
+
+pub fn make_dict<D: Dictionary>(dict: D, options: &Options, pm: &PathManager) -> Result<()> {
+    let (_, source_pm, _) = pm.langs();
+    let mut progress = console_progress(options);
+
+    pm.setup_dirs()?;
+
+    let mut lines = Vec::new();
+    let mut entries = D::I::default();
+
+    for (edition, paths) in pm.paths_jsonl() {
+        let path_jsonl = match &options.input {
+            Some(path_input) => path_input.clone(),
+            None => find_or_download_jsonl(edition, source_pm, &paths, options)?,
+        };
+        tracing::debug!("path_jsonl: {}", path_jsonl.display());
+
+        let mut reader = open_jsonl(&path_jsonl)?;
+        let read = if options.incremental {
+            read_jsonl_incremental
+        } else {
+            read_jsonl
+        };
+        read(
+            &dict,
+            edition,
+            pm,
+            options,
+            &mut reader,
+            &mut lines,
+            &mut entries,
+            progress.as_mut(),
+        )?;
+    }
+
+    finish_dict(&dict, options, pm, entries, progress.as_mut())
+}
+
+/// Read, filter and process every line of `reader`, merging the results into `entries`.
+///
+/// `lines` is a buffer that can be reused between calls.
+#[allow(clippy::too_many_arguments)]
+fn read_jsonl<D: Dictionary>(
+    dict: &D,
+    edition: EditionLang,
+    pm: &PathManager,
+    options: &Options,
+    reader: &mut impl BufRead,
+    lines: &mut Vec<Vec<u8>>,
+    entries: &mut D::I,
+    progress: &mut dyn Progress,
+) -> Result<()> {
+    let (_, source_pm, target_pm) = pm.langs();
+    let jobs = options.worker_count();
+    let batch_size = jobs * LINES_PER_JOB;
+    let linkages = options.kept_linkages();
+
+    let mut line_count = 0;
+    let mut accepted_count = 0;
+
+    loop {
+        let n_read = read_lines(reader, lines, batch_size)?;
+        if n_read == 0 {
+            break; // EOF
         }
+        line_count += n_read;
+
+        let mut word_entries: Vec<_> =
+            parse_lines(&mut lines[..n_read], jobs, &linkages, |entry| {
+                (!rejected(entry, options)).then_some(())
+            })?
+            .into_iter()
+            .map(|(entry, ())| entry)
+            .collect();
+
+        let reached_first = take_first(options.first, &mut accepted_count, &mut word_entries);
+
+        process_word_entries(
+            dict,
+            edition,
+            source_pm,
+            target_pm,
+            options,
+            &mut word_entries,
+            entries,
+        );
 
-        if !options.quiet {
-            println!("Processed {line_count} lines. Accepted {accepted_count} lines.");
+        progress.report(ProgressEvent::Lines {
+            processed: line_count,
+        });
+
+        if reached_first || n_read < batch_size {
+            break;
         }
     }
 
-    if !options.quiet {
-        dict.found_ir_message(&entries);
-    }
+    progress.report(ProgressEvent::LinesDone {
+        processed: line_count,
+        accepted: Some(accepted_count),
+    });
+
+    Ok(())
+}
+
+/// Postprocess the intermediate representation and write it, together with the dictionary.
+fn finish_dict<D: Dictionary>(
+    dict: &D,
+    options: &Options,
+    pm: &PathManager,
+    mut entries: D::I,
+    progress: &mut dyn Progress,
+) -> Result<()> {
+    progress.report(ProgressEvent::Found {
+        message: &dict.found_ir_message(&entries),
+    });
 
     if entries.is_empty() {
         return Ok(());
@@ -411,18 +768,29 @@ pub fn make_dict<D: Dictionary>(dict: D, options: &Options, pm: &PathManager) ->
 
     if !options.skip_yomitan {
         let mut diagnostics = Diagnostics::default();
-
-        // HACK: This unwrap_or is only for GlossaryExtended and works as a filler
-        // because the edition is not used in the implementation of to_yomitan for that dict.
-        // It is basically here to not crash the code. Happy face.
-        let ed = edition_pm.try_into().unwrap_or(EditionLang::En);
-        let labelled_entries =
-            dict.to_yomitan(ed, source_pm, target_pm, options, &mut diagnostics, entries);
+        let labelled_entries = convert_to_yomitan(dict, options, pm, &mut diagnostics, entries);
 
         dict.write_diagnostics(pm, &diagnostics)?;
 
-        write_yomitan(source_pm, target_pm, options, pm, &labelled_entries)?;
+        write_yomitan(options, pm, &labelled_entries, progress)?;
     }
 
     Ok(())
 }
+
+/// Convert the intermediate representation to yomitan entries.
+fn convert_to_yomitan<D: Dictionary>(
+    dict: &D,
+    options: &Options,
+    pm: &PathManager,
+    diagnostics: &mut Diagnostics,
+    entries: D::I,
+) -> Vec<LabelledYomitanEntry> {
+    let (edition_pm, source_pm, target_pm) = pm.langs();
+
+    // HACK: This unwrap_or is only for GlossaryExtended and works as a filler
+    // because the edition is not used in the implementation of to_yomitan for that dict.
+    // It is basically here to not crash the code. Happy face.
+    let ed = edition_pm.try_into().unwrap_or(EditionLang::En);
+    dict.to_yomitan(ed, source_pm, target_pm, options, diagnostics, entries)
+}
@@ -1,15 +1,18 @@
 use crate::{
     Map, Set,
     cli::Options,
-    dict::{Diagnostics, Dictionary, LabelledYomitanEntry, get_ipas, get_reading},
+    dict::{Diagnostics, Dictionary, LabelledYomitanEntry, get_ipas, get_reading, get_readings},
+    freq::FreqList,
     lang::{EditionLang, Lang},
     models::{
-        kaikki::WordEntry,
+        kaikki::{Sound, WordEntry},
         yomitan::{
-            DetailedDefinition, NTag, Node, PhoneticTranscription, TermBank, TermBankMeta,
-            TermPhoneticTranscription, YomitanEntry, wrap,
+            DetailedDefinition, Frequency, NTag, Node, PhoneticTranscription, Pitch, PitchAccents,
+            TermBank, TermBankMeta, TermFrequency, TermPhoneticTranscription, TermPitch,
+            YomitanEntry, wrap,
         },
     },
+    path::DictionaryType,
     tags::find_short_pos,
 };
 
@@ -25,8 +28,15 @@ pub struct DIpa;
 #[derive(Debug, Clone, Copy)]
 pub struct DIpaMerged;
 
+#[derive(Debug, Clone, Copy)]
+pub struct DFreq;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DPitch;
+
 impl Dictionary for DGlossary {
     type I = Vec<YomitanEntry>;
+    const DICT_TYPE: DictionaryType = DictionaryType::Glossary;
 
     fn process(
         &self,
@@ -54,6 +64,7 @@ impl Dictionary for DGlossary {
 
 impl Dictionary for DGlossaryExtended {
     type I = Vec<IGlossaryExtended>;
+    const DICT_TYPE: DictionaryType = DictionaryType::GlossaryExtended;
 
     fn process(
         &self,
@@ -96,6 +107,7 @@ impl Dictionary for DGlossaryExtended {
 
 impl Dictionary for DIpa {
     type I = Vec<IIpa>;
+    const DICT_TYPE: DictionaryType = DictionaryType::Ipa;
 
     fn process(
         &self,
@@ -123,6 +135,7 @@ impl Dictionary for DIpa {
 
 impl Dictionary for DIpaMerged {
     type I = Vec<IIpa>;
+    const DICT_TYPE: DictionaryType = DictionaryType::IpaMerged;
 
     fn process(
         &self,
@@ -155,6 +168,76 @@ impl Dictionary for DIpaMerged {
     }
 }
 
+impl Dictionary for DFreq {
+    type I = Vec<IFreq>;
+    const DICT_TYPE: DictionaryType = DictionaryType::Freq;
+
+    fn process(
+        &self,
+        edition: EditionLang,
+        source: Lang,
+        _: Lang,
+        entry: &WordEntry,
+        irs: &mut Self::I,
+    ) {
+        process_freq(edition, source, entry, irs);
+    }
+
+    fn postprocess(&self, irs: &mut Self::I) {
+        // Keep only unique entries
+        *irs = Set::from_iter(irs.drain(..)).into_iter().collect();
+    }
+
+    fn to_yomitan(
+        &self,
+        _: EditionLang,
+        _: Lang,
+        _: Lang,
+        options: &Options,
+        _: &mut Diagnostics,
+        irs: Self::I,
+    ) -> Vec<LabelledYomitanEntry> {
+        let freq_list = options
+            .freq_list
+            .as_deref()
+            .expect("the frequency list should be loaded by prepare_command");
+        vec![("term", to_yomitan_freq(freq_list, irs))]
+    }
+}
+
+impl Dictionary for DPitch {
+    type I = Vec<IPitch>;
+    const DICT_TYPE: DictionaryType = DictionaryType::Pitch;
+
+    fn process(
+        &self,
+        edition: EditionLang,
+        source: Lang,
+        _: Lang,
+        entry: &WordEntry,
+        irs: &mut Self::I,
+    ) {
+        process_pitch(edition, source, entry, irs);
+    }
+
+    fn postprocess(&self, irs: &mut Self::I) {
+        // Keep only unique entries
+        *irs = Set::from_iter(irs.drain(..)).into_iter().collect();
+    }
+
+    fn to_yomitan(
+        &self,
+        _: EditionLang,
+        _: Lang,
+        _: Lang,
+        _: &Options,
+        _: &mut Diagnostics,
+        irs: Self::I,
+    ) -> Vec<LabelledYomitanEntry> {
+        vec![("term", to_yomitan_pitch(irs))]
+    }
+}
+
 // rg: process translations processtranslations
 fn process_glossary(
     source: EditionLang,
@@ -221,6 +304,9 @@ fn process_glossary(
         found_pos.clone(),
         found_pos,
         definitions,
+        0,
+        -1,
+        String::new(),
     )));
 }
 
@@ -292,6 +378,9 @@ fn to_yomitan_glossary_extended(irs: Vec<IGlossaryExtended>) -> Vec<YomitanEntry
                     .into_iter()
                     .map(DetailedDefinition::Text)
                     .collect(),
+                0,
+                -1,
+                String::new(),
             ))
         })
         .collect()
@@ -325,6 +414,202 @@ fn to_yomitan_ipa(irs: Vec<IIpa>) -> Vec<YomitanEntry> {
         .collect()
 }
 
+type IFreq = (String, String); // (lemma, reading)
+
+fn process_freq(edition: EditionLang, source: Lang, word_entry: &WordEntry, irs: &mut Vec<IFreq>) {
+    let reading =
+        get_reading(edition, source, word_entry).unwrap_or_else(|| word_entry.word.clone());
+    irs.push((word_entry.word.clone(), reading));
+}
+
+/// Only keep the words of the dictionary that appear in the frequency list, with their ranks.
+fn to_yomitan_freq(freq_list: &FreqList, irs: Vec<IFreq>) -> Vec<YomitanEntry> {
+    irs.into_iter()
+        .filter_map(|(lemma, reading)| {
+            let frequency = freq_list.rank(&lemma)?;
+            let frequency = if reading == lemma {
+                Frequency::Value(frequency)
+            } else {
+                Frequency::WithReading { reading, frequency }
+            };
+            Some(YomitanEntry::TermBankMeta(TermBankMeta::TermFrequency(
+                TermFrequency(lemma, "freq".to_string(), frequency),
+            )))
+        })
+        .collect()
+}
+
+type IPitch = (String, PitchAccents);
+
+const DOWNSTEP_MARK: char = 'ꜜ';
+const NASAL_MARK: char = '\u{309A}'; // combining handakuten: か゚
+const DEVOICE_MARK: char = '\u{0325}'; // combining ring below: す̥
+
+/// Tags giving the accent pattern, used when the kana has no downstep mark.
+const ACCENT_TAGS: [&str; 4] = ["Heiban", "Atamadaka", "Nakadaka", "Odaka"];
+
+fn process_pitch(
+    edition: EditionLang,
+    source: Lang,
+    word_entry: &WordEntry,
+    irs: &mut Vec<IPitch>,
+) {
+    let mut readings = get_readings(edition, source, word_entry);
+    if readings.is_empty() {
+        readings.push(word_entry.word.clone());
+    }
+
+    // Sounds may have different readings (ex. 五色: ごしき, ごしょく)
+    let mut pitches_by_reading: Map<String, Vec<Pitch>> = Map::default();
+    for sound in &word_entry.sounds {
+        let Some((kana, pitch)) = parse_pitch(sound) else {
+            continue;
+        };
+        let pitch_reading = match readings
+            .iter()
+            .find(|reading| is_same_reading(&kana, reading))
+        {
+            Some(reading) => reading.clone(),
+            // Yomitan would never match a reading with ー against the term reading, and it is
+            // ambiguous (めー could be めい or めえ)
+            None if kana.contains('ー') => continue,
+            None => kana,
+        };
+        let pitches = pitches_by_reading.entry(pitch_reading).or_default();
+        if !pitches.contains(&pitch) {
+            pitches.push(pitch);
+        }
+    }
+
+    irs.extend(
+        pitches_by_reading
+            .into_iter()
+            .map(|(reading, pitches)| (word_entry.word.clone(), PitchAccents { reading, pitches })),
+    );
+}
+
+/// Parse the pitch accent of a sound, returning its kana (without marks) and pitch.
+///
+/// The position is given by the downstep mark if any (ex. たのしꜜい), and by the accent tags
+/// otherwise (ex. めーれー with the Heiban tag).
+fn parse_pitch(sound: &Sound) -> Option<(String, Pitch)> {
+    let text = sound.other.trim();
+    if text.is_empty()
+        || !text
+            .chars()
+            .all(|c| is_kana(c) || matches!(c, DOWNSTEP_MARK | NASAL_MARK | DEVOICE_MARK))
+    {
+        return None;
+    }
+
+    let mut kana = String::new();
+    let mut moras: usize = 0;
+    let mut downstep = None;
+    let mut nasal = Vec::new();
+    let mut devoice = Vec::new();
+
+    for c in text.chars() {
+        match c {
+            DOWNSTEP_MARK => downstep = Some(moras),
+            // The nasal mark is written over the unvoiced kana: か゚ reads が
+            NASAL_MARK => {
+                let voiced = kana
+                    .pop()
+                    .and_then(|prev| char::from_u32(prev as u32 + 1))?;
+                kana.push(voiced);
+                nasal.push(moras.checked_sub(1)?);
+            }
+            DEVOICE_MARK => devoice.push(moras.checked_sub(1)?),
+            c => {
+                kana.push(c);
+                if !is_small_kana(c) {
+                    moras += 1;
+                }
+            }
+        }
+    }
+
+    let accent_tag = sound
+        .tags
+        .iter()
+        .find(|tag| ACCENT_TAGS.contains(&tag.as_str()));
+    let position = match (downstep, accent_tag.map(String::as_str)) {
+        (Some(position), _) => position,
+        (None, Some("Heiban")) => 0,
+        (None, Some("Atamadaka")) => 1,
+        (None, Some("Odaka")) => moras,
+        _ => return None,
+    };
+
+    let mut tags: Vec<_> = sound
+        .tags
+        .iter()
+        .filter(|tag| !ACCENT_TAGS.contains(&tag.as_str()))
+        .cloned()
+        .collect();
+    if !sound.note.is_empty() {
+        tags.push(sound.note.clone());
+    }
+
+    let pitch = Pitch {
+        position,
+        nasal,
+        devoice,
+        tags,
+    };
+    Some((kana, pitch))
+}
+
+const fn is_kana(c: char) -> bool {
+    matches!(c, '\u{3041}'..='\u{3096}' | '\u{30A1}'..='\u{30FA}' | 'ー')
+}
+
+/// Small kana that do not make a mora on their own (but っ does).
+const fn is_small_kana(c: char) -> bool {
+    matches!(
+        c,
+        'ぁ' | 'ぃ'
+            | 'ぅ'
+            | 'ぇ'
+            | 'ぉ'
+            | 'ゃ'
+            | 'ゅ'
+            | 'ょ'
+            | 'ゎ'
+            | 'ァ'
+            | 'ィ'
+            | 'ゥ'
+            | 'ェ'
+            | 'ォ'
+            | 'ャ'
+            | 'ュ'
+            | 'ョ'
+            | 'ヮ'
+    )
+}
+
+/// Whether `kana` is a pronunciation spelling of `reading`, where long vowels may be written with
+/// ー (ex. めーれー for めいれい).
+fn is_same_reading(kana: &str, reading: &str) -> bool {
+    kana.chars().count() == reading.chars().count()
+        && kana
+            .chars()
+            .zip(reading.chars())
+            .all(|(k, r)| k == r || k == 'ー')
+}
+
+fn to_yomitan_pitch(irs: Vec<IPitch>) -> Vec<YomitanEntry> {
+    irs.into_iter()
+        .map(|(lemma, pitch_accents)| {
+            YomitanEntry::TermBankMeta(TermBankMeta::TermPitch(TermPitch(
+                lemma,
+                "pitch".to_string(),
+                pitch_accents,
+            )))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -406,6 +691,76 @@ mod tests {
         }
     }
 
+    fn pitch_of(other: &str, tags: &[&str]) -> Option<(String, Pitch)> {
+        let sound = Sound {
+            other: other.into(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            ..Default::default()
+        };
+        parse_pitch(&sound)
+    }
+
+    #[test]
+    fn parse_pitch_basic() {
+        let (kana, pitch) = pitch_of("たのしꜜい", &["Tokyo"]).unwrap();
+        assert_eq!(kana, "たのしい");
+        assert_eq!(pitch.position, 3);
+        assert_eq!(pitch.tags, vec!["Tokyo"]);
+
+        // Small kana do not count as moras
+        let (_, pitch) = pitch_of("きょうꜜと", &[]).unwrap();
+        assert_eq!(pitch.position, 2);
+
+        let (kana, pitch) = pitch_of("めーれー", &["Heiban", "Tokyo"]).unwrap();
+        assert_eq!(kana, "めーれー");
+        assert_eq!(pitch.position, 0);
+        assert!(is_same_reading(&kana, "めいれい"));
+
+        let (kana, pitch) = pitch_of("か\u{309A}くせい", &["Heiban"]).unwrap();
+        assert_eq!(kana, "がくせい");
+        assert_eq!(pitch.nasal, vec![0]);
+
+        let (_, pitch) = pitch_of("す\u{0325}ꜜき", &[]).unwrap();
+        assert_eq!(pitch.position, 1);
+        assert_eq!(pitch.devoice, vec![0]);
+
+        // No accent information, or not kana
+        assert!(pitch_of("たぬき", &[]).is_none());
+        assert!(pitch_of("[súꜜkì]", &[]).is_none());
+    }
+
+    #[test]
+    fn process_pitch_readings() {
+        let pitches_of = |word: &str, sounds: &[(&str, &[&str])]| {
+            let mut word_entry = WordEntry::default();
+            word_entry.word = word.into();
+            word_entry.sounds = sounds
+                .iter()
+                .map(|(other, tags)| Sound {
+                    other: (*other).into(),
+                    tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                    ..Default::default()
+                })
+                .collect();
+            let mut irs = Vec::new();
+            process_pitch(EditionLang::En, Lang::Ja, &word_entry, &mut irs);
+            irs.into_iter()
+                .map(|(_, accents)| (accents.reading, accents.pitches.len()))
+                .collect::<Vec<_>>()
+        };
+
+        // ー is matched against the reading of the term
+        assert_eq!(
+            pitches_of("きょう", &[("きょꜜー", &[])]),
+            [("きょう".into(), 1)]
+        );
+
+        // A different reading is kept, unless it has ー, which can not be resolved
+        let sounds: &[(&str, &[&str])] = &[("めーれー", &["Heiban"]), ("めいれい", &["Heiban"])];
+        assert_eq!(pitches_of("命令", sounds), [("めいれい".into(), 1)]);
+        assert!(pitches_of("命令", &[("めーれー", &["Heiban"])]).is_empty());
+    }
+
     #[test]
     fn process_ipa_merged_basic() {
         let dict = DIpaMerged;
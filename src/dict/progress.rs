@@ -0,0 +1,127 @@
+//! Progress reporting, so that embedders of kty can decide what to do with it.
+//!
+//! The CLI prints to the console (`ConsoleProgress`), while a library user may forward events to
+//! a log, a channel etc. Any `FnMut(ProgressEvent)` closure is a `Progress`.
+
+use std::io::Write;
+use std::path::Path;
+
+use crate::utils::{CHECK_C, pretty_print_at_path, pretty_println_at_path};
+
+/// Something that happened while making a dictionary.
+#[derive(Debug, Clone, Copy)]
+pub enum ProgressEvent<'a> {
+    /// Some more lines of the jsonlines were processed. `processed` is the running total.
+    Lines { processed: usize },
+    /// The jsonlines was fully read.
+    ///
+    /// `accepted` is the number of lines that passed the filters, if it was tracked.
+    LinesDone {
+        processed: usize,
+        accepted: Option<usize>,
+    },
+    /// With `--incremental`, how many lines were taken from the cache of the previous run, and
+    /// how many had to be processed.
+    Cached {
+        reused: usize,
+        processed: usize,
+        path: &'a Path,
+    },
+    /// Summary of the entries found, before postprocessing. Cf. `Dictionary::found_ir_message`.
+    Found { message: &'a str },
+    /// A term bank was written.
+    Bank {
+        /// Kind of entries in the bank (lemma, form, ipa...).
+        label: &'a str,
+        /// Position of the bank among those of the same label, starting at 1.
+        num: usize,
+        total: usize,
+        entries: usize,
+        /// Where the bank was written, if it was written to disk.
+        path: Option<&'a Path>,
+    },
+    /// The dictionary (or the temporary files, with `save_temps`) was written.
+    Written {
+        what: &'a str,
+        path: Option<&'a Path>,
+    },
+}
+
+pub trait Progress {
+    fn report(&mut self, event: ProgressEvent<'_>);
+}
+
+impl<F> Progress for F
+where
+    F: FnMut(ProgressEvent<'_>),
+{
+    fn report(&mut self, event: ProgressEvent<'_>) {
+        self(event);
+    }
+}
+
+/// Ignore every event.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoProgress;
+
+impl Progress for NoProgress {
+    fn report(&mut self, _: ProgressEvent<'_>) {}
+}
+
+/// Print events to the console. This is what the CLI uses.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConsoleProgress;
+
+impl Progress for ConsoleProgress {
+    fn report(&mut self, event: ProgressEvent<'_>) {
+        match event {
+            ProgressEvent::Lines { processed } => {
+                print!("Processed {processed} lines...\r");
+            }
+            ProgressEvent::LinesDone {
+                processed,
+                accepted: Some(accepted),
+            } => println!("Processed {processed} lines. Accepted {accepted} lines."),
+            ProgressEvent::LinesDone {
+                processed,
+                accepted: None,
+            } => println!("Processed {processed} lines."),
+            ProgressEvent::Cached {
+                reused,
+                processed,
+                path,
+            } => {
+                let msg = format!("Reused {reused} cached lines, processed {processed} lines");
+                pretty_println_at_path(&msg, path);
+            }
+            ProgressEvent::Found { message } => println!("{message}"),
+            ProgressEvent::Bank {
+                label,
+                num,
+                total,
+                entries,
+                path,
+            } => {
+                if num > 1 {
+                    print!("\r\x1b[K");
+                }
+                let msg = format!("Wrote yomitan {label} bank {num}/{total} ({entries} entries)");
+                match path {
+                    Some(path) => pretty_print_at_path(&msg, path),
+                    None => print!("{msg}"),
+                }
+                if num == total {
+                    println!();
+                }
+            }
+            ProgressEvent::Written { what, path } => {
+                let msg = format!("{CHECK_C} Wrote {what}");
+                match path {
+                    Some(path) => pretty_println_at_path(&msg, path),
+                    None => println!("{msg}"),
+                }
+            }
+        }
+        let _ = std::io::stdout().flush();
+    }
+}
@@ -0,0 +1,535 @@
+//! Edition-specific rules, expressed as data.
+//!
+//! Every edition has a bundled TOML file under `assets/rules`, and `common.toml` holds the rules
+//! that depend on the source language only. Any of them can be overridden with `--rules`, a TOML
+//! file with a `[common]` section and one section per edition. Every field of an edition section
+//! replaces the bundled one:
+//!
+//! ```toml
+//! [de.inflection]
+//! gloss = '^(?P<inflection>.*)des (?:Verbs|Adjektivs) (?P<lemma>.*)$'
+//! requires_form_of = false
+//! sense_tags = false
+//! fallback = false
+//! ```
+//!
+//! Cf. `EditionRules` for the meaning of every field.
+
+use anyhow::{Context, Ok, Result, bail, ensure};
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use std::path::Path;
+use std::sync::{LazyLock, OnceLock};
+
+use crate::Map;
+use crate::lang::{EditionLang, Lang};
+use crate::models::kaikki::Sense;
+
+const COMMON_RULES: &str = include_str!("../../assets/rules/common.toml");
+
+const EDITION_RULES: [(&str, &str); 21] = [
+    ("cs", include_str!("../../assets/rules/cs.toml")),
+    ("de", include_str!("../../assets/rules/de.toml")),
+    ("el", include_str!("../../assets/rules/el.toml")),
+    ("en", include_str!("../../assets/rules/en.toml")),
+    ("es", include_str!("../../assets/rules/es.toml")),
+    ("fr", include_str!("../../assets/rules/fr.toml")),
+    ("id", include_str!("../../assets/rules/id.toml")),
+    ("it", include_str!("../../assets/rules/it.toml")),
+    ("ja", include_str!("../../assets/rules/ja.toml")),
+    ("ko", include_str!("../../assets/rules/ko.toml")),
+    ("ku", include_str!("../../assets/rules/ku.toml")),
+    ("ms", include_str!("../../assets/rules/ms.toml")),
+    ("nl", include_str!("../../assets/rules/nl.toml")),
+    ("pl", include_str!("../../assets/rules/pl.toml")),
+    ("pt", include_str!("../../assets/rules/pt.toml")),
+    ("ru", include_str!("../../assets/rules/ru.toml")),
+    ("simple", include_str!("../../assets/rules/simple.toml")),
+    ("th", include_str!("../../assets/rules/th.toml")),
+    ("tr", include_str!("../../assets/rules/tr.toml")),
+    ("vi", include_str!("../../assets/rules/vi.toml")),
+    ("zh", include_str!("../../assets/rules/zh.toml")),
+];
+
+static BUNDLED_RULES: LazyLock<Rules> =
+    LazyLock::new(|| Rules::bundled().expect("bundled rules are valid"));
+
+/// The installed rules, with the content of the file they were read from.
+static INSTALLED_RULES: OnceLock<(String, Rules)> = OnceLock::new();
+
+/// The rules in use: the bundled ones, unless overrides were installed.
+pub fn rules() -> &'static Rules {
+    INSTALLED_RULES
+        .get()
+        .map_or(&BUNDLED_RULES, |(_, rules)| rules)
+}
+
+/// The content of every rule file in use: the bundled ones, then the overrides, if installed.
+pub fn rules_contents() -> impl Iterator<Item = &'static str> {
+    std::iter::once(COMMON_RULES)
+        .chain(EDITION_RULES.iter().map(|(_, content)| *content))
+        .chain(INSTALLED_RULES.get().map(|(content, _)| content.as_str()))
+}
+
+/// Override the bundled rules with those of the file at `path`.
+///
+/// This can only be done once per process, and must be done before making any dictionary.
+/// Installing the same rules again is a no-op (f.e. every dictionary of a batch shares the same
+/// `--rules`), but installing different ones is an error.
+pub fn install_rules(path: &Path) -> Result<()> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read rules {}", path.display()))?;
+    let ensure_same = |installed: &str| {
+        ensure!(
+            installed == content,
+            "Failed to install rules {}: different rules are already installed",
+            path.display()
+        );
+        Ok(())
+    };
+
+    if let Some((installed, _)) = INSTALLED_RULES.get() {
+        return ensure_same(installed);
+    }
+    let mut rules = Rules::bundled()?;
+    rules
+        .merge_toml(&content)
+        .with_context(|| format!("Failed to parse rules {}", path.display()))?;
+    if INSTALLED_RULES.set((content.clone(), rules)).is_err() {
+        // Installed concurrently
+        let (installed, _) = INSTALLED_RULES.get().expect("the rules are installed");
+        return ensure_same(installed);
+    }
+    Ok(())
+}
+
+/// A regex, deserialized from a string.
+#[derive(Debug, Clone)]
+pub struct Pattern(pub Regex);
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern)
+            .map(Pattern)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Rules {
+    pub common: CommonRules,
+    editions: Map<EditionLang, EditionRules>,
+}
+
+/// Rules that only depend on the source language.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommonRules {
+    /// Sources whose canonical form may contain extra diacritics, and is used as reading.
+    ///
+    /// For most languages, this is equal to word, but for, let's say, Latin, there may be a
+    /// difference (cf. <https://en.wiktionary.org/wiki/fama>, where the word is fama, but the
+    /// canonical form is fāma).
+    pub canonical_sources: Vec<String>,
+    /// Sources whose combining accents (U+0300..U+036F) are dropped when comparing forms.
+    pub strip_accents_sources: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditionRules {
+    /// How to get the reading, by source iso. The key `*` applies to every other source.
+    /// Sources without a strategy use `canonical`.
+    pub readings: Option<Map<String, ReadingStrategy>>,
+    /// Tags copied to every sense, in order.
+    pub propagate_tags: Option<Vec<TagPropagation>>,
+    /// How to detect and handle senses that are an inflection of some other word.
+    pub inflection: Option<InflectionRule>,
+    /// Stop extracting forms at the first of these forms, by source iso.
+    pub break_at_forms: Option<Map<String, Vec<String>>>,
+    /// Where to get the grammar summary shown before the glosses. Defaults to `template`.
+    pub head_info: Option<HeadInfoStrategy>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReadingStrategy {
+    /// The canonical form, for `canonical_sources` only.
+    Canonical,
+    /// The ruby of the canonical form.
+    Japanese,
+    /// The form tagged as romanization.
+    Romanization,
+    /// The form tagged as transliteration.
+    Transliteration,
+    /// The tone-marked Standard Mandarin pinyin in sounds, or the Jyutping for Cantonese.
+    /// Traditional and simplified variants also redirect to each other.
+    Chinese,
+    /// The word itself.
+    None,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HeadInfoStrategy {
+    /// The first head template expansion with parentheses.
+    #[default]
+    Template,
+    /// The gender and the forms of the head line (the ones without a `source` table).
+    Forms,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagPropagation {
+    pub from: TagSource,
+    /// Only propagate these tags (all if empty).
+    #[serde(default)]
+    pub only: Vec<String>,
+    /// Never propagate these tags.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TagSource {
+    /// The tags of the canonical form.
+    CanonicalForm,
+    /// The tags of the forms equal to the word.
+    WordForms,
+    /// The tags of the word entry.
+    Entry,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InflectionRule {
+    /// A sense is an inflection if any of its glosses matches one of these patterns (a single
+    /// pattern can be given as a string).
+    ///
+    /// The `lemma` group, if any, is the uninflected word (otherwise taken from `form_of`), and
+    /// the `inflection` group describes the inflection.
+    #[serde(default, deserialize_with = "one_or_many")]
+    pub gloss: Vec<Pattern>,
+    /// Whether to match the glosses joined with a space, instead of one by one.
+    ///
+    /// Useful when the inflection is in a subsense, f.e. "inflection of přít:" followed by
+    /// "feminine singular past active participle".
+    #[serde(default)]
+    pub join_glosses: bool,
+    /// Whether the `lemma` group must be one of the words of `form_of` for the gloss to match.
+    #[serde(default)]
+    pub lemma_in_form_of: bool,
+    /// Whether the sense must have a non empty `form_of`.
+    #[serde(default = "default_true")]
+    pub requires_form_of: bool,
+    /// Whether to use the tags of the sense to describe the inflection, before the `inflection`
+    /// group.
+    #[serde(default = "default_true")]
+    pub sense_tags: bool,
+    /// Only keep these sense tags (all if empty).
+    #[serde(default)]
+    pub retained_tags: Vec<String>,
+    /// Never keep these sense tags.
+    #[serde(default = "default_ignored_tags")]
+    pub ignored_tags: Vec<String>,
+    /// Whether to fall back to a "redirected from" description, or skip the inflection.
+    #[serde(default = "default_true")]
+    pub fallback: bool,
+    /// Whether the inflected word is the canonical form, for `canonical_sources`.
+    #[serde(default)]
+    pub canonical_inflected: bool,
+}
+
+/// The groups of the gloss that made a sense an inflection. Cf. `InflectionRule::gloss`.
+#[derive(Debug, Default)]
+pub struct InflectionMatch {
+    pub inflection: Option<String>,
+    pub lemma: Option<String>,
+}
+
+fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Pattern>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(Pattern),
+        Many(Vec<Pattern>),
+    }
+
+    Result::Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(pattern) => vec![pattern],
+        OneOrMany::Many(patterns) => patterns,
+    })
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+fn default_ignored_tags() -> Vec<String> {
+    vec!["form-of".to_string()]
+}
+
+/// What an override file looks like. Cf. the module documentation.
+#[derive(Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    common: Option<CommonRules>,
+    #[serde(flatten)]
+    editions: Map<String, EditionRules>,
+}
+
+impl Rules {
+    fn bundled() -> Result<Self> {
+        let mut rules = Self {
+            common: toml::from_str(COMMON_RULES).context("Failed to parse common rules")?,
+            editions: Map::default(),
+        };
+        for (iso, content) in EDITION_RULES {
+            let edition: EditionLang = iso.parse().map_err(anyhow::Error::msg)?;
+            let edition_rules: EditionRules =
+                toml::from_str(content).with_context(|| format!("Failed to parse {iso} rules"))?;
+            edition_rules.validate()?;
+            rules.editions.insert(edition, edition_rules);
+        }
+        Ok(rules)
+    }
+
+    fn merge_toml(&mut self, content: &str) -> Result<()> {
+        let file: RulesFile = toml::from_str(content)?;
+        if let Some(common) = file.common {
+            self.common = common;
+        }
+        for (iso, overrides) in file.editions {
+            let Result::Ok(edition) = iso.parse::<EditionLang>() else {
+                bail!("Unknown edition [{iso}]");
+            };
+            overrides.validate()?;
+            self.editions.entry(edition).or_default().merge(overrides);
+        }
+        Ok(())
+    }
+
+    pub fn edition(&self, edition: EditionLang) -> &EditionRules {
+        static EMPTY: EditionRules = EditionRules {
+            readings: None,
+            propagate_tags: None,
+            inflection: None,
+            break_at_forms: None,
+            head_info: None,
+        };
+        self.editions.get(&edition).unwrap_or(&EMPTY)
+    }
+}
+
+impl CommonRules {
+    pub fn is_canonical_source(&self, source: Lang) -> bool {
+        self.canonical_sources
+            .iter()
+            .any(|iso| iso == source.as_ref())
+    }
+
+    pub fn strips_accents(&self, source: Lang) -> bool {
+        self.strip_accents_sources
+            .iter()
+            .any(|iso| iso == source.as_ref())
+    }
+}
+
+impl EditionRules {
+    fn merge(&mut self, other: Self) {
+        macro_rules! merge_fields {
+            ($($field:ident),*) => { $( if other.$field.is_some() { self.$field = other.$field; } )* };
+        }
+        merge_fields!(
+            readings,
+            propagate_tags,
+            inflection,
+            break_at_forms,
+            head_info
+        );
+    }
+
+    /// Check that every key is a known iso.
+    fn validate(&self) -> Result<()> {
+        let readings = self.readings.iter().flat_map(Map::keys);
+        let break_at_forms = self.break_at_forms.iter().flat_map(Map::keys);
+        for iso in readings.chain(break_at_forms) {
+            if iso != "*" && iso.parse::<Lang>().is_err() {
+                bail!("Unknown language '{iso}' in rules");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn reading(&self, source: Lang) -> ReadingStrategy {
+        self.readings
+            .as_ref()
+            .and_then(|readings| readings.get(source.as_ref()).or_else(|| readings.get("*")))
+            .copied()
+            .unwrap_or(ReadingStrategy::Canonical)
+    }
+
+    pub fn propagate_tags(&self) -> &[TagPropagation] {
+        self.propagate_tags.as_deref().unwrap_or_default()
+    }
+
+    pub fn head_info(&self) -> HeadInfoStrategy {
+        self.head_info.unwrap_or_default()
+    }
+
+    pub fn break_at_forms(&self, source: Lang) -> &[String] {
+        self.break_at_forms
+            .as_ref()
+            .and_then(|forms| forms.get(source.as_ref()))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+impl TagPropagation {
+    pub fn accepts(&self, tag: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|t| t == tag))
+            && !self.exclude.iter().any(|t| t == tag)
+    }
+}
+
+impl InflectionRule {
+    /// Return the groups of the first matching gloss if the sense is an inflection.
+    pub fn matches(&self, sense: &Sense) -> Option<InflectionMatch> {
+        let form_of = || {
+            sense
+                .form_of
+                .iter()
+                .map(|form| form.word.as_str())
+                .filter(|word| !word.is_empty())
+        };
+        if self.requires_form_of && form_of().next().is_none() {
+            return None;
+        }
+        if self.gloss.is_empty() {
+            return Some(InflectionMatch::default());
+        }
+
+        let joined;
+        let glosses = if self.join_glosses {
+            joined = [sense.glosses.join(" ")];
+            &joined[..]
+        } else {
+            &sense.glosses[..]
+        };
+
+        glosses.iter().find_map(|gloss| {
+            self.gloss.iter().find_map(|re| {
+                let caps = re.0.captures(gloss)?;
+                let group = |name: &str| {
+                    caps.name(name)
+                        .map(|m| m.as_str().trim())
+                        .filter(|m| !m.is_empty())
+                        .map(ToString::to_string)
+                };
+                let lemma = group("lemma");
+                if self.lemma_in_form_of
+                    && !lemma
+                        .as_deref()
+                        .is_some_and(|lemma| form_of().any(|word| word == lemma))
+                {
+                    return None;
+                }
+                Some(InflectionMatch {
+                    inflection: group("inflection"),
+                    lemma,
+                })
+            })
+        })
+    }
+
+    /// The tags of the sense that describe the inflection.
+    pub fn filter_sense_tags<'a>(&self, tags: &'a [String]) -> impl Iterator<Item = &'a String> {
+        tags.iter().filter(|tag| {
+            (self.retained_tags.is_empty() || self.retained_tags.contains(tag))
+                && !self.ignored_tags.contains(tag)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_rules_are_valid() {
+        let rules = Rules::bundled().unwrap();
+        assert!(rules.common.is_canonical_source(Lang::La));
+        assert_eq!(
+            rules.edition(EditionLang::En).reading(Lang::Ja),
+            ReadingStrategy::Japanese
+        );
+        assert_eq!(
+            rules.edition(EditionLang::Ja).reading(Lang::Ko),
+            ReadingStrategy::Transliteration
+        );
+        assert_eq!(
+            rules.edition(EditionLang::Fr).reading(Lang::Fr),
+            ReadingStrategy::Canonical
+        );
+        assert_eq!(
+            rules
+                .edition(EditionLang::En)
+                .break_at_forms(Lang::Fi)
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn overrides_replace_fields() {
+        let mut rules = Rules::bundled().unwrap();
+        rules
+            .merge_toml(
+                r#"
+                [common]
+                canonical_sources = ["la"]
+
+                [de.inflection]
+                gloss = '^(?P<inflection>.*) von (?P<lemma>.*)$'
+                "#,
+            )
+            .unwrap();
+        assert!(!rules.common.is_canonical_source(Lang::Ru));
+
+        let de = rules.edition(EditionLang::De);
+        let inflection = de.inflection.as_ref().unwrap();
+        assert!(inflection.gloss[0].0.is_match("Plural von Haus"));
+        assert!(inflection.sense_tags);
+        // Untouched
+        assert_eq!(de.reading(Lang::De), ReadingStrategy::Canonical);
+
+        assert!(rules.merge_toml("[xx.inflection]\ngloss = 'a'").is_err());
+        assert!(rules.merge_toml("[de.inflection]\ngloss = '('").is_err());
+        assert!(rules.merge_toml("[de.readings]\nxx = 'pinyin'").is_err());
+    }
+
+    #[test]
+    fn install_rules_once() {
+        let dir = std::env::temp_dir();
+        let path = |name: &str, content: &str| {
+            let path = dir.join(format!("kty-rules-{}-{name}.toml", std::process::id()));
+            std::fs::write(&path, content).unwrap();
+            path
+        };
+        // Empty, so that the rules of the other tests stay the bundled ones
+        let empty = path("empty", "");
+        let other = path("other", "[common]\ncanonical_sources = []\n");
+
+        install_rules(&empty).unwrap();
+        install_rules(&empty).unwrap();
+        assert!(install_rules(&other).is_err());
+        assert!(rules().common.is_canonical_source(Lang::La));
+
+        std::fs::remove_file(empty).unwrap();
+        std::fs::remove_file(other).unwrap();
+    }
+}
@@ -0,0 +1,388 @@
+//! Comparison of two builds of a dictionary, ex. before and after bumping the kaikki snapshot.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::Map;
+use crate::models::yomitan::{
+    DetailedDefinition, TagInformation, TermBank, TermBankMeta, YomitanEntry,
+};
+use crate::utils::{for_each_dictionary_file, human_size, size};
+
+/// An inflected form: the lemma and the inflections that lead to it.
+type Redirect = (String, Vec<String>);
+
+/// The content of a dictionary, by term.
+#[derive(Debug, Default)]
+struct Snapshot {
+    size: u64,
+    /// Entries with definitions.
+    terms: Map<String, Vec<TermBank>>,
+    /// Entries that only redirect to their lemmas (the `non-lemma` entries of the main dictionary).
+    redirects: Map<String, Vec<Redirect>>,
+    meta: Map<String, Vec<TermBankMeta>>,
+    /// By short tag.
+    tags: Map<String, TagInformation>,
+}
+
+impl Snapshot {
+    fn load(path: &Path) -> Result<Self> {
+        let mut snapshot = Self {
+            size: size(path).with_context(|| format!("could not open {}", path.display()))?,
+            ..Default::default()
+        };
+
+        for_each_dictionary_file(path, |file_name, bytes| {
+            if file_name.starts_with("tag_bank_") {
+                let tags: Vec<TagInformation> = serde_json::from_slice(bytes)
+                    .with_context(|| format!("{file_name} is not a valid tag bank"))?;
+                for tag in tags {
+                    snapshot.tags.insert(tag.short_tag.clone(), tag);
+                }
+            } else if file_name.starts_with("term_bank_")
+                || file_name.starts_with("term_meta_bank_")
+            {
+                let entries: Vec<YomitanEntry> = serde_json::from_slice(bytes)
+                    .with_context(|| format!("{file_name} is not a valid bank"))?;
+                entries.into_iter().for_each(|entry| snapshot.insert(entry));
+            }
+            Ok(())
+        })?;
+
+        snapshot.sort_rows();
+        Ok(snapshot)
+    }
+
+    /// Sort the rows of every term, so that they are compared regardless of their order.
+    ///
+    /// Terms are sorted by reading, then sequence, and their json breaks the ties.
+    fn sort_rows(&mut self) {
+        for terms in self.terms.values_mut() {
+            terms.sort_by_cached_key(|term| {
+                let json = serde_json::to_string(term).expect("terms serialize to json");
+                (term.1.clone(), term.6, json)
+            });
+        }
+        for redirects in self.redirects.values_mut() {
+            redirects.sort();
+        }
+        for metas in self.meta.values_mut() {
+            metas.sort_by_cached_key(|meta| {
+                serde_json::to_string(meta).expect("meta entries serialize to json")
+            });
+        }
+    }
+
+    fn insert(&mut self, entry: YomitanEntry) {
+        match entry {
+            YomitanEntry::TermBank(term) => {
+                if term.is_redirect() {
+                    let redirects = term.4.into_iter().filter_map(|def| match def {
+                        DetailedDefinition::Inflection(redirect) => Some(redirect),
+                        _ => None,
+                    });
+                    self.redirects.entry(term.0).or_default().extend(redirects);
+                } else {
+                    self.terms.entry(term.0.clone()).or_default().push(term);
+                }
+            }
+            YomitanEntry::TermBankMeta(meta) => {
+                self.meta
+                    .entry(meta.term().to_string())
+                    .or_default()
+                    .push(meta);
+            }
+        }
+    }
+}
+
+/// The keys that were added, removed or changed from one map to the other.
+#[derive(Debug)]
+struct Changes<'a> {
+    added: Vec<&'a str>,
+    removed: Vec<&'a str>,
+    changed: Vec<&'a str>,
+}
+
+impl<'a> Changes<'a> {
+    fn new<T: PartialEq>(old: &'a Map<String, T>, new: &'a Map<String, T>) -> Self {
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        for (key, new_value) in new {
+            match old.get(key) {
+                None => added.push(key.as_str()),
+                Some(old_value) if old_value != new_value => changed.push(key.as_str()),
+                Some(_) => (),
+            }
+        }
+        let removed = old
+            .keys()
+            .filter(|key| !new.contains_key(*key))
+            .map(String::as_str)
+            .collect();
+
+        Self {
+            added,
+            removed,
+            changed,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// The differences between two builds of a dictionary (zipped or directories with their banks).
+#[derive(Debug)]
+pub struct DictDiff {
+    old: Snapshot,
+    new: Snapshot,
+}
+
+impl DictDiff {
+    pub fn new(old: &Path, new: &Path) -> Result<Self> {
+        Ok(Self {
+            old: Snapshot::load(old)?,
+            new: Snapshot::load(new)?,
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        Changes::new(&self.old.terms, &self.new.terms).is_empty()
+            && Changes::new(&self.old.redirects, &self.new.redirects).is_empty()
+            && Changes::new(&self.old.meta, &self.new.meta).is_empty()
+            && Changes::new(&self.old.tags, &self.new.tags).is_empty()
+    }
+
+    /// One line per kind of entry, with the number of added, removed and changed terms.
+    pub fn summary(&self) -> String {
+        let (old, new) = (&self.old, &self.new);
+        let delta = new.size.abs_diff(old.size) as f64;
+        let sign = if new.size < old.size { '-' } else { '+' };
+
+        let mut out = format!(
+            "{:<10} {} → {} ({sign}{})\n",
+            "size",
+            human_size(old.size as f64),
+            human_size(new.size as f64),
+            human_size(delta)
+        );
+        summary_line(&mut out, "terms", &old.terms, &new.terms);
+        summary_line(&mut out, "redirects", &old.redirects, &new.redirects);
+        summary_line(&mut out, "meta", &old.meta, &new.meta);
+        summary_line(&mut out, "tags", &old.tags, &new.tags);
+        out
+    }
+
+    /// Every added (+), removed (-) and changed (~) term, with a line diff of the changed ones.
+    pub fn details(&self) -> String {
+        let (old, new) = (&self.old, &self.new);
+        let mut out = String::new();
+
+        details_section(&mut out, "terms", &old.terms, &new.terms, false, |terms| {
+            terms.iter().flat_map(TermBank::render).collect()
+        });
+        details_section(
+            &mut out,
+            "redirects",
+            &old.redirects,
+            &new.redirects,
+            true,
+            |redirects| {
+                redirects
+                    .iter()
+                    .map(|(lemma, inflections)| format!("{lemma} ({})", inflections.join(", ")))
+                    .collect()
+            },
+        );
+        details_section(&mut out, "meta", &old.meta, &new.meta, true, |metas| {
+            metas.iter().map(TermBankMeta::render).collect()
+        });
+        details_section(&mut out, "tags", &old.tags, &new.tags, true, |tag| {
+            vec![format!("{} ({})", tag.long_tag, tag.category)]
+        });
+
+        out
+    }
+}
+
+fn summary_line<T: PartialEq>(
+    out: &mut String,
+    name: &str,
+    old: &Map<String, T>,
+    new: &Map<String, T>,
+) {
+    let changes = Changes::new(old, new);
+    writeln!(
+        out,
+        "{name:<10} {} → {} ({} added, {} removed, {} changed)",
+        old.len(),
+        new.len(),
+        changes.added.len(),
+        changes.removed.len(),
+        changes.changed.len()
+    )
+    .unwrap();
+}
+
+/// If `inline`, added and removed entries are rendered next to their key, otherwise only the key
+/// is shown (full term entries would be too long).
+fn details_section<T: PartialEq>(
+    out: &mut String,
+    name: &str,
+    old: &Map<String, T>,
+    new: &Map<String, T>,
+    inline: bool,
+    render: impl Fn(&T) -> Vec<String>,
+) {
+    let changes = Changes::new(old, new);
+    if changes.is_empty() {
+        return;
+    }
+
+    writeln!(out, "{name}:").unwrap();
+    let mut write_key = |sign: char, key: &str, value: &T| {
+        if inline {
+            writeln!(out, "{sign} {key}: {}", render(value).join("; ")).unwrap();
+        } else {
+            writeln!(out, "{sign} {key}").unwrap();
+        }
+    };
+    for key in &changes.removed {
+        write_key('-', key, &old[*key]);
+    }
+    for key in &changes.added {
+        write_key('+', key, &new[*key]);
+    }
+
+    for key in &changes.changed {
+        writeln!(out, "~ {key}").unwrap();
+        let (old_lines, new_lines) = (render(&old[*key]), render(&new[*key]));
+        let lines = diff_lines(&old_lines, &new_lines);
+        if lines.is_empty() {
+            writeln!(out, "    (same text, different json)").unwrap();
+        }
+        for (sign, line) in lines {
+            writeln!(out, "    {sign} {line}").unwrap();
+        }
+    }
+}
+
+/// The lines removed (-) from `old` and added (+) in `new`, following their longest common
+/// subsequence.
+fn diff_lines<'a>(old: &'a [String], new: &'a [String]) -> Vec<(char, &'a str)> {
+    let (n, m) = (old.len(), new.len());
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            lines.push(('-', old[i].as_str()));
+            i += 1;
+        } else {
+            lines.push(('+', new[j].as_str()));
+            j += 1;
+        }
+    }
+    lines.extend(old[i..].iter().map(|line| ('-', line.as_str())));
+    lines.extend(new[j..].iter().map(|line| ('+', line.as_str())));
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::yomitan::{PhoneticTranscription, TermPhoneticTranscription};
+    use std::path::PathBuf;
+
+    fn fixture(path: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/dict")
+            .join(path)
+    }
+
+    #[test]
+    fn diff_lines_lcs() {
+        let old = ["a", "b", "c"].map(String::from);
+        let new = ["a", "c", "d"].map(String::from);
+        assert_eq!(diff_lines(&old, &new), [('-', "b"), ('+', "d")]);
+    }
+
+    #[test]
+    fn same_dictionary() {
+        for path in [
+            "fi/en/temp-main/dict",
+            "ja/ja/temp-pitch/dict",
+            "de/en/temp-glossary/dict",
+        ] {
+            let dir = fixture(path);
+            let diff = DictDiff::new(&dir, &dir).unwrap();
+            assert!(diff.is_empty(), "{path}");
+            assert_eq!(diff.details(), "");
+        }
+    }
+
+    #[test]
+    fn changed_dictionary() {
+        let dir = fixture("fi/en/temp-main/dict");
+        let mut diff = DictDiff::new(&dir, &dir).unwrap();
+        diff.new.terms.shift_remove("pää");
+        diff.new
+            .redirects
+            .insert("isompi".to_string(), vec![("iso".to_string(), vec![])]);
+
+        let summary = diff.summary();
+        assert!(summary.contains("terms      2 → 1 (0 added, 1 removed, 0 changed)"));
+        assert!(summary.contains("(0 added, 0 removed, 1 changed)"));
+
+        let details = diff.details();
+        assert!(details.contains("terms:\n- pää\n"));
+        assert!(details.contains("~ isompi\n    - iso (comparative)\n    + iso ()\n"));
+
+        // The same rows in another order are not a change
+        let mut diff = DictDiff::new(&dir, &dir).unwrap();
+        let iso = diff.old.terms["iso"][0].clone();
+        let mut other_reading = iso.clone();
+        other_reading.1 = "isot".to_string();
+        let mut other_sequence = iso.clone();
+        other_sequence.6 += 1;
+        diff.old.terms["iso"] = vec![iso.clone(), other_reading.clone(), other_sequence.clone()];
+        diff.new.terms["iso"] = vec![other_sequence, other_reading, iso];
+        let ipa = |reading: &str| {
+            TermBankMeta::TermPhoneticTranscription(TermPhoneticTranscription(
+                "iso".to_string(),
+                "ipa".to_string(),
+                PhoneticTranscription {
+                    reading: reading.to_string(),
+                    transcriptions: vec![],
+                },
+            ))
+        };
+        let metas = vec![ipa("iso"), ipa("isot")];
+        diff.old.meta.insert("iso".to_string(), metas.clone());
+        diff.new
+            .meta
+            .insert("iso".to_string(), metas.into_iter().rev().collect());
+        diff.old.sort_rows();
+        diff.new.sort_rows();
+        assert!(diff.is_empty(), "{}", diff.details());
+    }
+}
@@ -46,7 +46,8 @@ mod html {
     /// "Raw" means that it does not include extra information that they (kaikki) use for the
     /// website generation, but are not intended for the general use.
     ///
-    /// Does not write the .gz file to disk.
+    /// Does not write the .gz file to disk, unless `path_jsonl` itself has a `.gz` extension, in
+    /// which case the download is written as is, without decompressing it.
     pub fn download_jsonl(
         edition: EditionLang,
         source: Lang,
@@ -64,13 +65,17 @@ mod html {
             tracing::info!("Download was last modified: {:?}", last_modified);
         }
 
-        let reader = response.into_body().into_reader();
-        // We can't use gzip's ureq feature because there is no content-encoding in headers
-        // https://github.com/tatuylonen/wiktextract/issues/1482
-        let mut decoder = GzDecoder::new(reader);
-
+        let mut reader = response.into_body().into_reader();
         let mut writer = BufWriter::new(File::create(path_jsonl)?);
-        std::io::copy(&mut decoder, &mut writer)?;
+
+        if path_jsonl.extension().is_some_and(|ext| ext == "gz") {
+            std::io::copy(&mut reader, &mut writer)?;
+        } else {
+            // We can't use gzip's ureq feature because there is no content-encoding in headers
+            // https://github.com/tatuylonen/wiktextract/issues/1482
+            let mut decoder = GzDecoder::new(reader);
+            std::io::copy(&mut decoder, &mut writer)?;
+        }
 
         if !quiet {
             pretty_println_at_path(&format!("{CHECK_C} Downloaded"), path_jsonl);
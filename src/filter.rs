@@ -0,0 +1,529 @@
+//! Filter expressions of `--where`, to select entries on any field of a `WordEntry`.
+//!
+//! ```text
+//! pos in [noun, verb] && senses.tags contains archaic && word ~ /^un/
+//! senses.topics == medicine || !sounds
+//! ```
+//!
+//! Fields are paths into the (serialized) `WordEntry`, going through lists: `senses.tags` are the
+//! tags of every sense. A condition holds if any of the values of its field does:
+//!
+//! - `field == value`, `field != value`: equality (`!=` holds if no value is equal)
+//! - `field in [a, b]`: equality with one of the values of the list
+//! - `field contains value`: substring
+//! - `field ~ /regex/`, `field !~ /regex/`: regex search
+//! - `field`: the field has a non-empty value, ex. `sounds` or `translations`
+//!
+//! Conditions can be combined with `&&`, `||`, `!` and parentheses. Values are either quoted
+//! (`"a b"`) or bare words.
+
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde_json::Value;
+
+use std::cell::OnceCell;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+use std::sync::LazyLock;
+
+use crate::models::kaikki::WordEntry;
+
+/// A `WordEntry` with one element in every list of structs, to check the fields of a path.
+///
+/// Fields that are skipped when empty must be given a value.
+static SKELETON: LazyLock<Value> = LazyLock::new(|| {
+    let linkages = r#""synonyms": [{}], "antonyms": [{}], "hypernyms": [{}], "derived": [{}],
+        "related": [{}], "coordinate_terms": [{}]"#;
+    let json = format!(
+        r#"{{
+        "head_templates": [{{}}], "etymology_templates": [{{}}], "sounds": [{{}}],
+        "forms": [{{}}], "form_of": [{{}}], "alt_of": [{{}}], "translations": [{{}}], {linkages},
+        "senses": [{{
+            "examples": [{{ "translation": "_", "ref": "_" }}],
+            "form_of": [{{}}], "alt_of": [{{}}], {linkages}
+        }}]
+    }}"#
+    );
+    let entry: WordEntry = serde_json::from_str(&json).expect("skeleton is a valid entry");
+    serde_json::to_value(entry).expect("entries serialize to json")
+});
+
+/// A path to a field, ex. `senses.tags`.
+#[derive(Debug, Clone)]
+struct Field(Vec<String>);
+
+impl Field {
+    fn parse(path: &str) -> Result<Self> {
+        let keys: Vec<_> = path.split('.').map(str::to_string).collect();
+
+        let mut node = &*SKELETON;
+        for (idx, key) in keys.iter().enumerate() {
+            while let Value::Array(items) = node
+                && let Some(first) = items.first()
+            {
+                node = first;
+            }
+            node = node.get(key).with_context(|| {
+                let parent = keys[..idx].join(".");
+                match node.as_object() {
+                    Some(object) => format!(
+                        "unknown field '{key}'{}. Choose between: {}",
+                        if parent.is_empty() {
+                            String::new()
+                        } else {
+                            format!(" in '{parent}'")
+                        },
+                        object.keys().cloned().collect::<Vec<_>>().join(" | ")
+                    ),
+                    None => format!("'{parent}' has no fields"),
+                }
+            })?;
+        }
+
+        Ok(Self(keys))
+    }
+
+    /// The values of this field in `entry`, going through lists.
+    fn values<'a>(&self, entry: &'a Value) -> Vec<&'a Value> {
+        fn collect<'a>(node: &'a Value, keys: &[String], out: &mut Vec<&'a Value>) {
+            if let Value::Array(items) = node {
+                for item in items {
+                    collect(item, keys, out);
+                }
+                return;
+            }
+            match keys.split_first() {
+                None => out.push(node),
+                Some((key, rest)) => {
+                    if let Some(child) = node.get(key) {
+                        collect(child, rest, out);
+                    }
+                }
+            }
+        }
+
+        let mut out = Vec::new();
+        collect(entry, &self.0, &mut out);
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Condition {
+    Eq(String),
+    In(Vec<String>),
+    Contains(String),
+    Matches(Regex),
+}
+
+impl Condition {
+    fn holds(&self, value: &Value) -> bool {
+        let text = match value {
+            Value::String(text) => text.as_str(),
+            Value::Number(_) | Value::Bool(_) => &value.to_string(),
+            Value::Null | Value::Array(_) | Value::Object(_) => return false,
+        };
+        match self {
+            Self::Eq(expected) => text == expected,
+            Self::In(expected) => expected.iter().any(|value| value == text),
+            Self::Contains(sub) => text.contains(sub.as_str()),
+            Self::Matches(re) => re.is_match(text),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    /// The field has a non-empty value.
+    Present(Field),
+    Condition(Field, Condition),
+}
+
+impl Node {
+    fn mentions(&self, key: &str) -> bool {
+        match self {
+            Self::And(lhs, rhs) | Self::Or(lhs, rhs) => lhs.mentions(key) || rhs.mentions(key),
+            Self::Not(expr) => expr.mentions(key),
+            Self::Present(field) | Self::Condition(field, _) => field.0.iter().any(|k| k == key),
+        }
+    }
+
+    /// Whether a serialized `WordEntry` matches this expression.
+    fn eval(&self, entry: &Value) -> bool {
+        match self {
+            Self::And(lhs, rhs) => lhs.eval(entry) && rhs.eval(entry),
+            Self::Or(lhs, rhs) => lhs.eval(entry) || rhs.eval(entry),
+            Self::Not(expr) => !expr.eval(entry),
+            Self::Present(field) => field.values(entry).into_iter().any(|value| match value {
+                Value::Null => false,
+                Value::Bool(b) => *b,
+                Value::Number(_) => true,
+                Value::String(text) => !text.is_empty(),
+                Value::Array(items) => !items.is_empty(),
+                Value::Object(object) => !object.is_empty(),
+            }),
+            Self::Condition(field, condition) => field
+                .values(entry)
+                .into_iter()
+                .any(|value| condition.holds(value)),
+        }
+    }
+}
+
+/// Whether `entry` matches every expression.
+///
+/// The expressions are evaluated on `entry` serialized to json, which is stored in `json` the
+/// first time it is needed, so that the expressions of several dictionaries share it.
+pub fn matches_all(exprs: &[Expr], entry: &WordEntry, json: &OnceCell<Value>) -> bool {
+    if exprs.is_empty() {
+        return true;
+    }
+    let json = json.get_or_init(|| serde_json::to_value(entry).expect("entries serialize to json"));
+    exprs.iter().all(|expr| expr.0.eval(json))
+}
+
+/// A parsed `--where` expression.
+#[derive(Debug, Clone)]
+pub struct Expr(Node);
+
+impl Expr {
+    /// Whether a field of this expression goes through `key`, ex. `senses.synonyms.word` through
+    /// `synonyms`.
+    pub fn mentions(&self, key: &str) -> bool {
+        self.0.mentions(key)
+    }
+}
+
+impl FromStr for Expr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let expr = parser.or()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {token}");
+        }
+        Ok(Self(expr))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A bare word: a field, a keyword (`in`, `contains`) or a value.
+    Word(String),
+    Quoted(String),
+    Regex(String),
+    And,
+    Or,
+    Not,
+    Eq,
+    Ne,
+    Match,
+    NotMatch,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Word(word) => write!(f, "'{word}'"),
+            Self::Quoted(text) => write!(f, "\"{text}\""),
+            Self::Regex(re) => write!(f, "/{re}/"),
+            Self::And => write!(f, "'&&'"),
+            Self::Or => write!(f, "'||'"),
+            Self::Not => write!(f, "'!'"),
+            Self::Eq => write!(f, "'=='"),
+            Self::Ne => write!(f, "'!='"),
+            Self::Match => write!(f, "'~'"),
+            Self::NotMatch => write!(f, "'!~'"),
+            Self::LParen => write!(f, "'('"),
+            Self::RParen => write!(f, "')'"),
+            Self::LBracket => write!(f, "'['"),
+            Self::RBracket => write!(f, "']'"),
+            Self::Comma => write!(f, "','"),
+        }
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':')
+}
+
+/// Read until an unescaped `end`, which is consumed.
+///
+/// Only `end` is unescaped, since a regex has escapes of its own, except in quoted values where
+/// backslashes can be escaped too.
+fn delimited(chars: &mut Peekable<Chars>, end: char) -> Result<String> {
+    let mut text = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next) if next == end || (end == '"' && next == '\\') => text.push(next),
+                Some(next) => text.extend([c, next]),
+                None => text.push(c),
+            },
+            c if c == end => return Ok(text),
+            c => text.push(c),
+        }
+    }
+    bail!("missing closing {end}")
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '"' => Token::Quoted(delimited(&mut chars, '"')?),
+            '/' => Token::Regex(delimited(&mut chars, '/')?),
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            ',' => Token::Comma,
+            '~' => Token::Match,
+            '&' if chars.next_if_eq(&'&').is_some() => Token::And,
+            '|' if chars.next_if_eq(&'|').is_some() => Token::Or,
+            '=' if chars.next_if_eq(&'=').is_some() => Token::Eq,
+            '!' if chars.next_if_eq(&'=').is_some() => Token::Ne,
+            '!' if chars.next_if_eq(&'~').is_some() => Token::NotMatch,
+            '!' => Token::Not,
+            c if is_word_char(c) => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.next_if(|&c| is_word_char(c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+            c => bail!("unexpected '{c}'"),
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent parser, from the lowest precedence (`||`) to the highest (`!`).
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .context("unexpected end")?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &Token) -> Result<()> {
+        match self.next()? {
+            found if found == *token => Ok(()),
+            found => bail!("expected {token}, found {found}"),
+        }
+    }
+
+    fn or(&mut self) -> Result<Node> {
+        let mut expr = self.and()?;
+        while self.eat(&Token::Or) {
+            expr = Node::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Node> {
+        let mut expr = self.unary()?;
+        while self.eat(&Token::And) {
+            expr = Node::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        match self.next()? {
+            Token::Not => Ok(Node::Not(Box::new(self.unary()?))),
+            Token::LParen => {
+                let expr = self.or()?;
+                self.expect(&Token::RParen)?;
+                Ok(expr)
+            }
+            Token::Word(path) => self.condition(Field::parse(&path)?),
+            token => bail!("expected a field, found {token}"),
+        }
+    }
+
+    fn condition(&mut self, field: Field) -> Result<Node> {
+        let condition = match self.peek() {
+            Some(Token::Eq) => {
+                self.pos += 1;
+                Condition::Eq(self.value()?)
+            }
+            Some(Token::Ne) => {
+                self.pos += 1;
+                let condition = Condition::Eq(self.value()?);
+                return Ok(Node::Not(Box::new(Node::Condition(field, condition))));
+            }
+            Some(Token::Match) => {
+                self.pos += 1;
+                Condition::Matches(self.regex()?)
+            }
+            Some(Token::NotMatch) => {
+                self.pos += 1;
+                let condition = Condition::Matches(self.regex()?);
+                return Ok(Node::Not(Box::new(Node::Condition(field, condition))));
+            }
+            Some(Token::Word(word)) if word == "in" => {
+                self.pos += 1;
+                Condition::In(self.list()?)
+            }
+            Some(Token::Word(word)) if word == "contains" => {
+                self.pos += 1;
+                Condition::Contains(self.value()?)
+            }
+            _ => return Ok(Node::Present(field)),
+        };
+        Ok(Node::Condition(field, condition))
+    }
+
+    fn value(&mut self) -> Result<String> {
+        match self.next()? {
+            Token::Word(value) | Token::Quoted(value) => Ok(value),
+            token => bail!("expected a value, found {token}"),
+        }
+    }
+
+    fn regex(&mut self) -> Result<Regex> {
+        match self.next()? {
+            Token::Regex(re) | Token::Quoted(re) => {
+                Regex::new(&re).with_context(|| format!("invalid regex /{re}/"))
+            }
+            token => bail!("expected a /regex/, found {token}"),
+        }
+    }
+
+    fn list(&mut self) -> Result<Vec<String>> {
+        self.expect(&Token::LBracket)?;
+        let mut values = Vec::new();
+        while !self.eat(&Token::RBracket) {
+            if !values.is_empty() {
+                self.expect(&Token::Comma)?;
+            }
+            values.push(self.value()?);
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> WordEntry {
+        serde_json::from_str(
+            r#"{
+                "word": "unhealthy",
+                "pos": "adj",
+                "lang_code": "en",
+                "sounds": [{ "ipa": "/ʌnˈhɛlθi/" }],
+                "senses": [
+                    { "glosses": ["Not healthy."], "tags": ["archaic"] },
+                    { "glosses": ["Causing disease."], "topics": ["medicine"] }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn matches(expr: &str) -> bool {
+        let expr: Expr = expr.parse().unwrap();
+        matches_all(&[expr], &entry(), &OnceCell::new())
+    }
+
+    #[test]
+    fn conditions() {
+        assert!(matches("pos == adj"));
+        assert!(matches("pos != noun"));
+        assert!(matches("pos in [noun, adj]"));
+        assert!(!matches("pos in [noun, verb]"));
+        assert!(matches("word ~ /^un/"));
+        assert!(matches("word !~ /^re/"));
+        assert!(matches(r#"senses.glosses contains "disease""#));
+        assert!(matches("senses.topics == medicine"));
+        assert!(matches(r#"senses.glosses == "Not healthy.""#));
+        assert!(matches(r"senses.glosses ~ /^Not\s\w+\.$/"));
+        assert!(!matches(r#"word == "un\"healthy""#));
+        assert!(!matches("tags contains archaic"));
+    }
+
+    #[test]
+    fn presence() {
+        assert!(matches("sounds"));
+        assert!(matches("sounds.ipa"));
+        assert!(!matches("translations"));
+        assert!(!matches("forms"));
+        assert!(!matches("sounds.roman"));
+    }
+
+    #[test]
+    fn combinations() {
+        assert!(matches(
+            r#"pos in [adj, verb] && senses.tags contains "archaic" && word ~ /^un/"#
+        ));
+        assert!(matches("pos == noun || senses.topics == medicine"));
+        assert!(!matches("!(pos == noun || senses.topics == medicine)"));
+        // && binds tighter than ||
+        assert!(matches("pos == adj || pos == noun && translations"));
+        assert!(!matches("(pos == adj || pos == noun) && translations"));
+    }
+
+    #[test]
+    fn errors() {
+        for (expr, err) in [
+            (
+                "wrod == foo",
+                "unknown field 'wrod'. Choose between: alt_of | antonyms",
+            ),
+            ("senses.topic", "unknown field 'topic' in 'senses'"),
+            ("word.foo", "'word' has no fields"),
+            ("pos ==", "unexpected end"),
+            ("pos in noun", "expected '[', found 'noun'"),
+            ("word ~ /(/", "invalid regex /(/"),
+            ("word ~ /a", "missing closing /"),
+            (r#"word == "a"#, "missing closing \""),
+            ("pos == adj pos", "unexpected 'pos'"),
+            ("pos = adj", "unexpected '='"),
+        ] {
+            let found = expr.parse::<Expr>().unwrap_err().to_string();
+            assert!(found.starts_with(err), "{expr}: {found}");
+        }
+    }
+}
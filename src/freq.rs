@@ -0,0 +1,167 @@
+//! Frequency lists, used for frequency dictionaries and to score main dictionary entries.
+//!
+//! The expected format is a TSV with one word per line, followed by its count or rank:
+//!
+//! ```text
+//! der     7043928
+//! die     6829311
+//! und     4201932
+//! ```
+//!
+//! Empty lines and lines starting with `#` are ignored. In rank mode, the second column may be
+//! omitted, in which case the rank is given by the order of the lines. Given ranks are kept as
+//! they are, while counts are turned into ranks from 1 to n.
+
+use anyhow::{Context, Ok, Result, bail};
+use clap::ValueEnum;
+
+use std::fmt;
+use std::path::Path;
+
+use crate::Map;
+
+/// What the second column of a frequency list means.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum FreqMode {
+    /// Number of occurrences: the higher, the more frequent
+    #[default]
+    Count,
+    /// Position in the list: the lower, the more frequent (starting at 1)
+    Rank,
+}
+
+/// A frequency list, normalized to ranks.
+#[derive(Default)]
+pub struct FreqList {
+    ranks: Map<String, u32>,
+    /// The rank of the least frequent word.
+    max_rank: u32,
+}
+
+// Do not print the whole list when debugging options
+impl fmt::Debug for FreqList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FreqList({} words)", self.ranks.len())
+    }
+}
+
+impl FreqList {
+    pub fn load(path: &Path, mode: FreqMode) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read frequency list {}", path.display()))?;
+        Self::parse(&content, mode)
+            .with_context(|| format!("Failed to parse frequency list {}", path.display()))
+    }
+
+    pub fn parse(content: &str, mode: FreqMode) -> Result<Self> {
+        let mut values: Vec<(&str, u64)> = Vec::new();
+
+        for (idx, line) in content.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = line.split('\t');
+            let word = columns.next().unwrap_or_default().trim();
+            let value = match (columns.next().map(str::trim), mode) {
+                (Some(value), _) => value
+                    .parse()
+                    .with_context(|| format!("line {}: invalid number '{value}'", idx + 1))?,
+                (None, FreqMode::Rank) => values.len() as u64 + 1,
+                (None, FreqMode::Count) => bail!("line {}: missing count", idx + 1),
+            };
+
+            values.push((word, value));
+        }
+
+        let mut ranks = Map::default();
+        match mode {
+            // Counts become ranks. Stable, so that ties keep the order of the list
+            FreqMode::Count => {
+                values.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+                for (word, _) in values {
+                    let rank = ranks.len() as u32 + 1;
+                    ranks.entry(word.to_string()).or_insert(rank);
+                }
+            }
+            // Ranks are kept as they are, with their gaps (ex. a filtered top n list)
+            FreqMode::Rank => {
+                for (word, rank) in values {
+                    let rank = u32::try_from(rank)
+                        .with_context(|| format!("rank {rank} of '{word}' is too large"))?;
+                    ranks.entry(word.to_string()).or_insert(rank);
+                }
+            }
+        }
+        let max_rank = ranks.values().copied().max().unwrap_or_default();
+
+        Ok(Self { ranks, max_rank })
+    }
+
+    pub fn len(&self) -> usize {
+        self.ranks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranks.is_empty()
+    }
+
+    /// Return the rank of `word`, starting at 1, falling back to its lowercase version.
+    pub fn rank(&self, word: &str) -> Option<u32> {
+        self.ranks
+            .get(word)
+            .or_else(|| self.ranks.get(&word.to_lowercase()))
+            .copied()
+    }
+
+    /// Yomitan score of `word`: positive if it appears in the list, higher if more frequent, and
+    /// 0 otherwise.
+    pub fn score(&self, word: &str) -> i32 {
+        self.rank(word).map_or(0, |rank| {
+            i32::try_from(self.max_rank - rank + 1).unwrap_or(i32::MAX)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_counts() {
+        let content = "# comment\nund\t42\nder\t100\n\ndie\t100\n";
+        let list = FreqList::parse(content, FreqMode::Count).unwrap();
+        assert_eq!(list.rank("der"), Some(1));
+        assert_eq!(list.rank("die"), Some(2));
+        assert_eq!(list.rank("und"), Some(3));
+        assert_eq!(list.rank("Und"), Some(3));
+        assert_eq!(list.rank("das"), None);
+
+        assert_eq!(list.score("der"), 3);
+        assert_eq!(list.score("und"), 1);
+        assert_eq!(list.score("das"), 0);
+
+        assert!(FreqList::parse("der\n", FreqMode::Count).is_err());
+        assert!(FreqList::parse("der\tmany\n", FreqMode::Count).is_err());
+    }
+
+    #[test]
+    fn parse_ranks() {
+        let list = FreqList::parse("der\ndie\nund\n", FreqMode::Rank).unwrap();
+        assert_eq!(list.rank("und"), Some(3));
+
+        let list = FreqList::parse("und\t3\nder\t1\ndie\t2\nder\t4\n", FreqMode::Rank).unwrap();
+        assert_eq!(list.rank("der"), Some(1));
+        assert_eq!(list.rank("und"), Some(3));
+        assert_eq!(list.len(), 3);
+
+        // Gaps are kept, and scores stay positive
+        let list = FreqList::parse("a\t1\nb\t5\nc\t100\n", FreqMode::Rank).unwrap();
+        assert_eq!(list.rank("a"), Some(1));
+        assert_eq!(list.rank("b"), Some(5));
+        assert_eq!(list.rank("c"), Some(100));
+        assert_eq!(list.score("a"), 100);
+        assert_eq!(list.score("c"), 1);
+    }
+}
@@ -1,12 +1,18 @@
 pub mod cli;
+pub mod config;
 pub mod diagnostic;
 pub mod dict;
+pub mod diff;
 pub mod download;
+pub mod filter;
+pub mod freq;
 pub mod lang;
+pub mod lookup;
 pub mod models;
 pub mod path;
 pub mod tags;
 pub mod utils;
+pub mod validate;
 
 pub use dict::make_dict;
 
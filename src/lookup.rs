@@ -0,0 +1,441 @@
+//! Looking up words in a built dictionary, rendered as text the way yomitan would show them.
+//!
+//! Inflected forms (the `non-lemma` entries of the main dictionary) are resolved to their lemmas.
+//! Banks are read as our own yomitan models, so only dictionaries built by kty are supported.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::models::kaikki::Tag;
+use crate::models::yomitan::{
+    DetailedDefinition, Frequency, NTag, Node, TermBank, TermBankMeta, YomitanEntry,
+};
+use crate::utils::for_each_dictionary_file;
+use crate::{Map, Set};
+use anyhow::{Context, Result};
+
+impl TermBank {
+    /// The `(lemma, [inflection, ...])` definitions of an inflected form.
+    fn deinflections(&self) -> impl Iterator<Item = &(String, Vec<String>)> {
+        self.4.iter().filter_map(|definition| match definition {
+            DetailedDefinition::Inflection(redirect) => Some(redirect),
+            _ => None,
+        })
+    }
+
+    /// Whether this entry only redirects to other terms.
+    pub fn is_redirect(&self) -> bool {
+        self.4
+            .iter()
+            .all(|definition| matches!(definition, DetailedDefinition::Inflection(_)))
+    }
+
+    fn tags(&self) -> Set<&str> {
+        self.2
+            .split_whitespace()
+            .chain(self.7.split_whitespace())
+            .collect()
+    }
+
+    /// The headword (with its reading and tags) followed by the indented definitions.
+    pub fn render(&self) -> Vec<String> {
+        let mut header = self.0.clone();
+        if !self.1.is_empty() && self.1 != self.0 {
+            header.push_str(&format!(" ({})", self.1));
+        }
+        let tags = self.tags();
+        if !tags.is_empty() {
+            header.push_str(&format!(" [{}]", Vec::from_iter(tags).join(", ")));
+        }
+
+        let mut lines = vec![header];
+        let definitions: Vec<_> = self
+            .4
+            .iter()
+            .filter(|def| !matches!(def, DetailedDefinition::Inflection(_)))
+            .collect();
+        for definition in &definitions {
+            let mut renderer = TextRenderer {
+                indent: 2,
+                ..Default::default()
+            };
+            if definitions.len() > 1 {
+                renderer.push_prefix("• ");
+            }
+            renderer.definition(definition);
+            lines.extend(renderer.finish());
+        }
+        lines
+    }
+}
+
+impl TermBankMeta {
+    pub fn term(&self) -> &str {
+        match self {
+            Self::TermPhoneticTranscription(ipa) => &ipa.0,
+            Self::TermFrequency(freq) => &freq.0,
+            Self::TermPitch(pitch) => &pitch.0,
+        }
+    }
+
+    /// One line, ex. `ipa: /ˈiso/, [ˈis̠o̞]`.
+    pub fn render(&self) -> String {
+        let tags = |tags: &[Tag]| match tags {
+            [] => String::new(),
+            tags => format!(" [{}]", tags.join(", ")),
+        };
+
+        match self {
+            Self::TermPhoneticTranscription(ipa) => {
+                let transcriptions: Vec<_> = ipa
+                    .2
+                    .transcriptions
+                    .iter()
+                    .map(|ipa| format!("{}{}", ipa.ipa, tags(&ipa.tags)))
+                    .collect();
+                format!("ipa: {}", transcriptions.join(", "))
+            }
+            Self::TermPitch(pitch) => {
+                let pitches: Vec<_> = pitch
+                    .2
+                    .pitches
+                    .iter()
+                    .map(|pitch| format!("[{}]{}", pitch.position, tags(&pitch.tags)))
+                    .collect();
+                format!("pitch: {} {}", pitch.2.reading, pitches.join(", "))
+            }
+            Self::TermFrequency(freq) => match &freq.2 {
+                Frequency::Value(value)
+                | Frequency::WithReading {
+                    frequency: value, ..
+                } => {
+                    format!("freq: {value}")
+                }
+            },
+        }
+    }
+}
+
+/// The entries of a lemma, and how the looked up word inflects it.
+#[derive(Debug, Default)]
+pub struct Lookup {
+    /// The looked up word.
+    pub word: String,
+    pub lemma: String,
+    /// Empty if the word is the lemma itself.
+    pub inflections: Vec<String>,
+    pub terms: Vec<TermBank>,
+    pub meta: Vec<TermBankMeta>,
+}
+
+impl fmt::Display for Lookup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.inflections.is_empty() {
+            writeln!(
+                f,
+                "{} → {} ({})",
+                self.word,
+                self.lemma,
+                self.inflections.join("; ")
+            )?;
+        }
+        if self.terms.is_empty() && self.meta.is_empty() {
+            writeln!(f, "{}: no entry", self.lemma)?;
+        }
+
+        for line in self.terms.iter().flat_map(TermBank::render) {
+            writeln!(f, "{line}")?;
+        }
+        for meta in &self.meta {
+            writeln!(f, "{}", meta.render())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Look up a word in one or more dictionaries (zipped or directories with their banks).
+///
+/// Every entry is returned grouped by lemma: first the word itself, if it has a proper entry or
+/// meta data (ex. IPA), then every lemma it is an inflection of.
+pub fn lookup(paths: &[&Path], word: &str) -> Result<Vec<Lookup>> {
+    let mut direct = Lookup {
+        word: word.to_string(),
+        lemma: word.to_string(),
+        ..Default::default()
+    };
+    let mut inflections: Map<String, Vec<String>> = Map::default();
+
+    for_each_entry(paths, |entry| match entry {
+        YomitanEntry::TermBank(term) => {
+            if term.0 != word && term.1 != word {
+                return;
+            }
+            for (lemma, rules) in term.deinflections() {
+                inflections
+                    .entry(lemma.clone())
+                    .or_default()
+                    .push(rules.join(" "));
+            }
+            if !term.is_redirect() {
+                direct.terms.push(term);
+            }
+        }
+        YomitanEntry::TermBankMeta(meta) => {
+            if meta.term() == word {
+                direct.meta.push(meta);
+            }
+        }
+    })?;
+
+    let mut found = Vec::new();
+    if !direct.terms.is_empty() || !direct.meta.is_empty() {
+        found.push(direct);
+    }
+    if inflections.is_empty() {
+        return Ok(found);
+    }
+
+    let mut lemmas: Map<String, Lookup> = inflections
+        .into_iter()
+        .map(|(lemma, inflections)| {
+            let lookup = Lookup {
+                word: word.to_string(),
+                lemma: lemma.clone(),
+                inflections,
+                ..Default::default()
+            };
+            (lemma, lookup)
+        })
+        .collect();
+
+    for_each_entry(paths, |entry| match entry {
+        YomitanEntry::TermBank(term) => {
+            if let Some(lookup) = lemmas.get_mut(&term.0)
+                && !term.is_redirect()
+            {
+                lookup.terms.push(term);
+            }
+        }
+        YomitanEntry::TermBankMeta(meta) => {
+            if let Some(lookup) = lemmas.get_mut(meta.term()) {
+                lookup.meta.push(meta);
+            }
+        }
+    })?;
+
+    found.extend(lemmas.into_values());
+    Ok(found)
+}
+
+/// Call `f` on every entry of the (term and term meta) banks of these dictionaries, one bank at a
+/// time, so that the whole dictionary is never in memory.
+fn for_each_entry(paths: &[&Path], mut f: impl FnMut(YomitanEntry)) -> Result<()> {
+    for path in paths {
+        for_each_dictionary_file(path, |file_name, bytes| {
+            if file_name.starts_with("term_bank_") || file_name.starts_with("term_meta_bank_") {
+                let entries: Vec<YomitanEntry> = serde_json::from_slice(bytes)
+                    .with_context(|| format!("{file_name} is not a valid bank"))?;
+                entries.into_iter().for_each(&mut f);
+            }
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// Render structured content as indented lines of text.
+///
+/// Blocks (div, li, details...) start a new line, while inline nodes (span, a...) are
+/// concatenated. Lists are numbered, and the content of details is indented below their summary,
+/// as if every details was expanded.
+#[derive(Debug, Default)]
+struct TextRenderer {
+    lines: Vec<String>,
+    line: String,
+    /// Indentation of the current line.
+    line_indent: usize,
+    /// Indentation of the next line.
+    indent: usize,
+    /// List marker waiting for the first text of a list item, and its indentation.
+    prefix: Option<(String, usize)>,
+    /// The counters of the ordered lists we are in (None for unordered lists).
+    lists: Vec<Option<usize>>,
+}
+
+impl TextRenderer {
+    fn definition(&mut self, definition: &DetailedDefinition) {
+        match definition {
+            DetailedDefinition::Text(text) => self.text(text),
+            DetailedDefinition::StructuredContent(content) => self.node(content.content()),
+            DetailedDefinition::Inflection(_) => (),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        for (idx, part) in text.split('\n').enumerate() {
+            if idx > 0 {
+                self.newline();
+            }
+            if self.line.is_empty() {
+                let part = part.trim_start();
+                if part.is_empty() {
+                    continue;
+                }
+                match self.prefix.take() {
+                    Some((prefix, indent)) => {
+                        self.line_indent = indent;
+                        self.line = prefix;
+                    }
+                    None => self.line_indent = self.indent,
+                }
+                self.line.push_str(part);
+            } else {
+                self.line.push_str(part);
+            }
+        }
+    }
+
+    fn newline(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            let indent = " ".repeat(self.line_indent);
+            self.lines.push(format!("{indent}{}", line.trim_end()));
+        }
+    }
+
+    /// Start a new line with this prefix, and indent the following lines to align with it.
+    fn push_prefix(&mut self, prefix: &str) {
+        self.newline();
+        self.prefix = Some((prefix.to_string(), self.indent));
+        self.indent += prefix.chars().count();
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::Text(text) => self.text(text),
+            Node::Array(nodes) => nodes.iter().for_each(|node| self.node(node)),
+            Node::Backlink(link) => self.text(link.content()),
+            Node::Link(link) => self.text(link.content()),
+            Node::Generic(generic) => {
+                let content = &generic.content;
+                match generic.tag {
+                    tag @ (NTag::Ol | NTag::Ul) => {
+                        self.newline();
+                        self.lists.push((tag == NTag::Ol).then_some(0));
+                        self.node(content);
+                        self.lists.pop();
+                    }
+                    NTag::Li => {
+                        let indent = self.indent;
+                        let prefix = match self.lists.last_mut() {
+                            Some(Some(counter)) => {
+                                *counter += 1;
+                                format!("{counter}. ")
+                            }
+                            _ => "• ".to_string(),
+                        };
+                        self.push_prefix(&prefix);
+                        self.node(content);
+                        self.newline();
+                        self.prefix = None;
+                        self.indent = indent;
+                    }
+                    NTag::Details => {
+                        let indent = self.indent;
+                        self.newline();
+                        self.node(content);
+                        self.newline();
+                        self.indent = indent;
+                    }
+                    NTag::Summary => {
+                        self.newline();
+                        self.node(content);
+                        self.newline();
+                        self.indent += 2;
+                    }
+                    NTag::Div => {
+                        self.newline();
+                        self.node(content);
+                        self.newline();
+                    }
+                    NTag::Span => self.node(content),
+                }
+            }
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        self.newline();
+        self.lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::{Value, json};
+    use std::path::PathBuf;
+
+    fn render(node: &Value) -> String {
+        let node: Node = serde_json::from_value(node.clone()).unwrap();
+        let mut renderer = TextRenderer::default();
+        renderer.node(&node);
+        renderer.finish().join("\n")
+    }
+
+    #[test]
+    fn render_structured_content() {
+        let content = json!([
+            { "tag": "div", "content": [
+                { "tag": "details", "content": [
+                    { "tag": "summary", "content": "Etymology" },
+                    { "tag": "div", "content": ["From ", { "tag": "a", "href": "?query=isä", "content": "isä" }, "."] }
+                ]}
+            ]},
+            { "tag": "ol", "content": [
+                { "tag": "li", "content": { "tag": "div", "content": [
+                    { "tag": "span", "content": "(informal) " },
+                    "big",
+                    { "tag": "details", "content": [
+                        { "tag": "summary", "content": "1 example" },
+                        { "tag": "div", "content": [
+                            { "tag": "div", "content": "iso poika" },
+                            { "tag": "div", "content": "big boy" }
+                        ]}
+                    ]}
+                ]}},
+                { "tag": "li", "content": "great" }
+            ]}
+        ]);
+        let expected = "\
+Etymology
+  From isä.
+1. (informal) big
+   1 example
+     iso poika
+     big boy
+2. great";
+        assert_eq!(render(&content), expected);
+    }
+
+    #[test]
+    fn lookup_inflected_form() {
+        let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/dict/fi/en");
+        let main = dir.join("temp-main/dict");
+        let ipa = dir.join("temp-ipa/dict");
+
+        let found = lookup(&[&main, &ipa], "isompi").unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].lemma, "iso");
+        assert_eq!(found[0].inflections, ["comparative"]);
+
+        let rendered = found[0].to_string();
+        assert!(rendered.starts_with("isompi → iso (comparative)\niso [adj]\n"));
+        assert!(rendered.contains("\n  1. big, large, great (of a great size)\n"));
+        assert!(rendered.ends_with("ipa: /ˈiso/, [ˈis̠o̞]\n"));
+
+        assert!(lookup(&[&main], "not a word").unwrap().is_empty());
+    }
+}
@@ -1,13 +1,19 @@
 use anyhow::Result;
+use std::path::PathBuf;
 use tracing_subscriber::EnvFilter;
 use tracing_subscriber::fmt::format::FmtSpan;
 
 use kty::cli::{Cli, Command, Langs, SimpleArgs};
-use kty::dict::{DGlossary, DGlossaryExtended, DIpa, DIpaMerged, DMain, make_dict};
-use kty::download::download_jsonl;
+use kty::dict::{
+    BatchDict, BatchItem, DFreq, DGlossary, DGlossaryExtended, DIpa, DIpaMerged, DMain, DPitch,
+    find_or_download_jsonl, make_dict, make_dicts,
+};
+use kty::diff::DictDiff;
 use kty::lang::{EditionLang, Lang};
+use kty::lookup::lookup;
 use kty::path::{DictionaryType, PathManager};
-use kty::utils::skip_because_file_exists;
+use kty::utils::pretty_println_at_path;
+use kty::validate::validate_path;
 
 fn setup_tracing(verbose: bool) {
     // tracing_subscriber::fmt::init();
@@ -31,6 +37,43 @@ fn setup_tracing(verbose: bool) {
         .init();
 }
 
+/// Map a prepared dictionary command to its batch counterpart.
+fn batch_dict(cmd: &Command) -> Box<dyn BatchDict> {
+    match cmd {
+        Command::Main(args) => BatchItem::boxed(
+            DMain,
+            args.options(),
+            PathManager::new(DictionaryType::Main, args),
+        ),
+        Command::Glossary(args) => BatchItem::boxed(
+            DGlossary,
+            args.options(),
+            PathManager::new(DictionaryType::Glossary, args),
+        ),
+        Command::Ipa(args) => BatchItem::boxed(
+            DIpa,
+            args.options(),
+            PathManager::new(DictionaryType::Ipa, args),
+        ),
+        Command::IpaMerged(args) => BatchItem::boxed(
+            DIpaMerged,
+            &args.options,
+            PathManager::new(DictionaryType::IpaMerged, args),
+        ),
+        Command::Pitch(args) => BatchItem::boxed(
+            DPitch,
+            args.options(),
+            PathManager::new(DictionaryType::Pitch, args),
+        ),
+        Command::Freq(args) => BatchItem::boxed(
+            DFreq,
+            args.options(),
+            PathManager::new(DictionaryType::Freq, args),
+        ),
+        _ => unreachable!("not a batch dictionary: {cmd:?}"),
+    }
+}
+
 fn run_command(cmd: &Command) -> Result<()> {
     match cmd {
         Command::Main(args) => {
@@ -53,6 +96,18 @@ fn run_command(cmd: &Command) -> Result<()> {
             let pm = PathManager::new(DictionaryType::IpaMerged, args);
             make_dict(DIpaMerged, &args.options, &pm)
         }
+        Command::Pitch(args) => {
+            let pm = PathManager::new(DictionaryType::Pitch, args);
+            make_dict(DPitch, args.options(), &pm)
+        }
+        Command::Freq(args) => {
+            let pm = PathManager::new(DictionaryType::Freq, args);
+            make_dict(DFreq, args.options(), &pm)
+        }
+        Command::Batch(args) => {
+            let dicts = args.commands.iter().map(batch_dict).collect();
+            make_dicts(dicts, &args.options)
+        }
         Command::Download(args) => {
             let pm = PathManager::new(DictionaryType::Main, args);
             let langs = args.langs();
@@ -60,13 +115,9 @@ fn run_command(cmd: &Command) -> Result<()> {
             let edition_lang: EditionLang = langs.edition().try_into().unwrap();
             let opath = pm.path_jsonl(edition_lang, source);
 
-            if opath.exists() {
-                skip_because_file_exists("download", &opath);
-                Ok(())
-            } else {
-                let _ = std::fs::create_dir(pm.dir_kaik());
-                download_jsonl(edition_lang, source, &opath, args.options.quiet)
-            }
+            let _ = std::fs::create_dir(pm.dir_kaik());
+            find_or_download_jsonl(edition_lang, source, &[opath], &args.options)?;
+            Ok(())
         }
         Command::Iso(args) => {
             if args.edition {
@@ -76,6 +127,39 @@ fn run_command(cmd: &Command) -> Result<()> {
             }
             Ok(())
         }
+        Command::Validate(args) => {
+            let violations = validate_path(&args.path)?;
+            for violation in &violations {
+                println!("{violation}");
+            }
+            anyhow::ensure!(
+                violations.is_empty(),
+                "{} does not conform to the yomitan schemas ({} violations)",
+                args.path.display(),
+                violations.len()
+            );
+            pretty_println_at_path("Valid dictionary", &args.path);
+            Ok(())
+        }
+        Command::Lookup(args) => {
+            let paths: Vec<_> = std::iter::once(&args.path)
+                .chain(&args.others)
+                .map(PathBuf::as_path)
+                .collect();
+            let found = lookup(&paths, &args.word)?;
+            anyhow::ensure!(!found.is_empty(), "no entry for {}", args.word);
+            let rendered: Vec<_> = found.iter().map(ToString::to_string).collect();
+            print!("{}", rendered.join("\n"));
+            Ok(())
+        }
+        Command::Diff(args) => {
+            let diff = DictDiff::new(&args.old, &args.new)?;
+            if args.details {
+                print!("{}", diff.details());
+            }
+            print!("{}", diff.summary());
+            Ok(())
+        }
     }
 }
 
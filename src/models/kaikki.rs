@@ -10,15 +10,20 @@
 //! Example (el):
 //! <https://github.com/tatuylonen/wiktextract/blob/master/src/wiktextract/extractor/el/models.py>
 
-use serde::{Deserialize, Serialize};
+use clap::ValueEnum;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Deserializer, Serialize};
 
+use std::cell::Cell;
+
+use crate::Map;
 use crate::tags::{BLACKLISTED_FORM_TAGS, IDENTITY_FORM_TAGS};
 
 // In case we ever decide to narrow them
 pub type Tag = String;
 pub type Pos = String;
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct WordEntry {
     pub word: String,
@@ -31,6 +36,7 @@ pub struct WordEntry {
     // Not pub because unstable: use the getter method
     etymology_text: String, // En, El editions still use this
     etymology_texts: Vec<String>,
+    pub etymology_templates: Vec<EtymologyTemplate>,
 
     pub sounds: Vec<Sound>,
 
@@ -44,26 +50,52 @@ pub struct WordEntry {
     pub alt_of: Vec<AltForm>,
 
     pub translations: Vec<Translation>, // used in glossary
+
+    pub notes: Vec<String>, // usage notes, not in every edition
+
+    // Cf. LinkageKind
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Synonyms as u8 }, _>")]
+    pub synonyms: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Antonyms as u8 }, _>")]
+    pub antonyms: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Hypernyms as u8 }, _>")]
+    pub hypernyms: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Derived as u8 }, _>")]
+    pub derived: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Related as u8 }, _>")]
+    pub related: Vec<Linkage>,
+    #[serde(
+        deserialize_with = "deserialize_linkages::<{ LinkageKind::CoordinateTerms as u8 }, _>"
+    )]
+    pub coordinate_terms: Vec<Linkage>,
 }
 
 // To be avoided as much as possible: sort of internal field.
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct HeadTemplate {
     pub expansion: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
+pub struct EtymologyTemplate {
+    pub name: String,
+    pub args: Map<String, String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct Sound {
     pub ipa: String,
     pub tags: Vec<Tag>,
     pub note: String,
     pub zh_pron: String,
-    // pub other: String, // [ja]
+    pub other: String, // [ja] kana, with ꜜ marking the accent drop
+    pub roman: String, // [ja]
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct Sense {
     // Glosses are usually a one string vector, but when there's more, it follows:
@@ -75,6 +107,25 @@ pub struct Sense {
     pub alt_of: Vec<AltForm>,
     pub tags: Vec<Tag>,
     pub topics: Vec<Tag>,
+    /// Labels that wiktextract could not normalize into tags.
+    pub raw_tags: Vec<Tag>,
+    pub qualifier: String,
+
+    // Cf. LinkageKind
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Synonyms as u8 }, _>")]
+    pub synonyms: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Antonyms as u8 }, _>")]
+    pub antonyms: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Hypernyms as u8 }, _>")]
+    pub hypernyms: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Derived as u8 }, _>")]
+    pub derived: Vec<Linkage>,
+    #[serde(deserialize_with = "deserialize_linkages::<{ LinkageKind::Related as u8 }, _>")]
+    pub related: Vec<Linkage>,
+    #[serde(
+        deserialize_with = "deserialize_linkages::<{ LinkageKind::CoordinateTerms as u8 }, _>"
+    )]
+    pub coordinate_terms: Vec<Linkage>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default, Clone)]
@@ -88,21 +139,134 @@ pub struct Example {
     pub reference: String, // Reference of a quotation example
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+impl Example {
+    /// Quotations cite their source, while usage examples do not.
+    pub const fn is_quotation(&self) -> bool {
+        !self.reference.is_empty()
+    }
+}
+
+/// Which examples come first when a sense has more than we keep.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ExamplePreference {
+    /// Examples with translations
+    #[default]
+    Translated,
+    /// Quotations, then examples with translations
+    Quotations,
+    /// Usage examples, then examples with translations
+    Usage,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+#[serde(default)]
+pub struct Linkage {
+    pub word: String,
+}
+
+/// Lists of related words, found both at the entry and at the sense level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkageKind {
+    Synonyms,
+    Antonyms,
+    Hypernyms,
+    Derived,
+    Related,
+    CoordinateTerms,
+}
+
+impl LinkageKind {
+    pub const ALL: [Self; 6] = [
+        Self::Synonyms,
+        Self::Antonyms,
+        Self::Hypernyms,
+        Self::Derived,
+        Self::Related,
+        Self::CoordinateTerms,
+    ];
+
+    /// The field of `WordEntry` and `Sense` that holds these linkages.
+    pub const fn field(self) -> &'static str {
+        match self {
+            Self::Synonyms => "synonyms",
+            Self::Antonyms => "antonyms",
+            Self::Hypernyms => "hypernyms",
+            Self::Derived => "derived",
+            Self::Related => "related",
+            Self::CoordinateTerms => "coordinate_terms",
+        }
+    }
+
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+thread_local! {
+    /// Bitmask of the `LinkageKind`s to deserialize. Cf. `WordEntry::from_json`.
+    static KEPT_LINKAGES: Cell<u8> = const { Cell::new(u8::MAX) };
+}
+
+/// Deserialize the linkages of the `KIND` bit, or skip them if they are not kept.
+fn deserialize_linkages<'de, const KIND: u8, D>(deserializer: D) -> Result<Vec<Linkage>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if KEPT_LINKAGES.get() & (1 << KIND) == 0 {
+        IgnoredAny::deserialize(deserializer)?;
+        return Ok(Vec::new());
+    }
+    Vec::deserialize(deserializer)
+}
+
+macro_rules! impl_linkages {
+    ($ty:ty) => {
+        impl $ty {
+            pub const fn linkages(&self, kind: LinkageKind) -> &Vec<Linkage> {
+                match kind {
+                    LinkageKind::Synonyms => &self.synonyms,
+                    LinkageKind::Antonyms => &self.antonyms,
+                    LinkageKind::Hypernyms => &self.hypernyms,
+                    LinkageKind::Derived => &self.derived,
+                    LinkageKind::Related => &self.related,
+                    LinkageKind::CoordinateTerms => &self.coordinate_terms,
+                }
+            }
+
+            pub const fn linkages_mut(&mut self, kind: LinkageKind) -> &mut Vec<Linkage> {
+                match kind {
+                    LinkageKind::Synonyms => &mut self.synonyms,
+                    LinkageKind::Antonyms => &mut self.antonyms,
+                    LinkageKind::Hypernyms => &mut self.hypernyms,
+                    LinkageKind::Derived => &mut self.derived,
+                    LinkageKind::Related => &mut self.related,
+                    LinkageKind::CoordinateTerms => &mut self.coordinate_terms,
+                }
+            }
+        }
+    };
+}
+
+impl_linkages!(WordEntry);
+impl_linkages!(Sense);
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct AltForm {
     pub word: String,
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct Form {
     pub form: String,
     pub tags: Vec<Tag>,
     pub ruby: Vec<(String, String)>, // [ja] (kanji, hiragana)
+    pub source: String,              // [en] table of the form: empty for head line forms
 }
 
-#[derive(Debug, Deserialize, Serialize, Default)]
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
 #[serde(default)]
 pub struct Translation {
     pub lang_code: String,
@@ -115,6 +279,17 @@ pub struct Translation {
 // These should cover general functions usable for any dictionary and even for external users of
 // the WordEntry type.
 impl WordEntry {
+    /// Deserialize a jsonline, skipping the linkages of kinds not in `linkages`.
+    ///
+    /// Linkages are only shown with `--linkages`, and deserializing them all is expensive.
+    pub fn from_json(line: &[u8], linkages: &[LinkageKind]) -> serde_json::Result<Self> {
+        let kept = linkages.iter().fold(0, |mask, kind| mask | kind.bit());
+        let previous = KEPT_LINKAGES.replace(kept);
+        let word_entry = serde_json::from_slice(line);
+        KEPT_LINKAGES.set(previous);
+        word_entry
+    }
+
     // https://github.com/tatuylonen/wiktextract/pull/1489
     pub fn is_participle(&self) -> bool {
         self.pos == "verb" && self.tags.iter().any(|t| t == "participle")
@@ -132,24 +307,25 @@ impl WordEntry {
         self.tagged_forms(&["canonical"]).next()
     }
 
-    /// Return the first non-empty form with the `romanization` tag.
-    pub fn romanization_form(&self) -> Option<&Form> {
-        self.tagged_forms(&["romanization"]).next()
+    /// Return all non-empty forms with the `canonical` tag.
+    pub fn canonical_forms(&self) -> impl Iterator<Item = &Form> {
+        self.tagged_forms(&["canonical"])
     }
 
-    /// Return the first non-empty form with the `transliteration` tag.
-    pub fn transliteration_form(&self) -> Option<&Form> {
-        self.tagged_forms(&["transliteration"]).next()
+    /// Return all non-empty forms with the `romanization` tag.
+    pub fn romanization_forms(&self) -> impl Iterator<Item = &Form> {
+        self.tagged_forms(&["romanization"])
     }
 
-    /// Return the first `sound.zh_pron` with the `Pinyin` tag.
-    pub fn pinyin(&self) -> Option<&str> {
-        self.sounds.iter().find_map(|sound| {
-            if sound.tags.iter().any(|t| t == "Pinyin") {
-                Some(sound.zh_pron.as_ref())
-            } else {
-                None
-            }
+    /// Return all non-empty forms with the `transliteration` tag.
+    pub fn transliteration_forms(&self) -> impl Iterator<Item = &Form> {
+        self.tagged_forms(&["transliteration"])
+    }
+
+    /// Return all sounds with a non-empty `zh_pron` that contain all given tags.
+    pub fn tagged_zh_sounds<'a>(&'a self, tags: &[&str]) -> impl Iterator<Item = &'a Sound> {
+        self.sounds.iter().filter(|sound| {
+            !sound.zh_pron.is_empty() && tags.iter().all(|tag| sound.tags.iter().any(|t| t == tag))
         })
     }
 
@@ -208,3 +384,24 @@ impl WordEntry {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_json_skips_linkages() {
+        let line = br#"{"word": "a", "synonyms": [{"word": "b"}], "antonyms": [{"word": "c"}],
+            "senses": [{"glosses": ["d"], "synonyms": [{"word": "e"}]}]}"#;
+
+        let entry = WordEntry::from_json(line, &[LinkageKind::Antonyms]).unwrap();
+        assert!(entry.synonyms.is_empty());
+        assert!(entry.senses[0].synonyms.is_empty());
+        assert_eq!(entry.antonyms[0].word, "c");
+
+        // Plain deserialization keeps everything
+        let entry: WordEntry = serde_json::from_slice(line).unwrap();
+        assert_eq!(entry.synonyms[0].word, "b");
+        assert_eq!(entry.senses[0].synonyms[0].word, "e");
+    }
+}
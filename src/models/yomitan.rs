@@ -1,11 +1,12 @@
 use crate::{Map, models::kaikki::Tag};
+use serde::de::{self, Deserializer};
 use serde::ser::{SerializeTuple, Serializer};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum YomitanEntry {
-    TermBank(TermBank),         // 120 (24 * 5)
+    TermBank(TermBank),         // 160 (24 * 6 + 8 + 8)
     TermBankMeta(TermBankMeta), // 104
 }
 
@@ -18,22 +19,23 @@ impl YomitanEntry {
     }
 }
 
-// Simplified version to avoid storing fields that we don't use. Those are written later on via the
-// serialize implementation.
-//
-// The skipped fields are (at index): frequency (4), sequence (6), term_tags (7)
+// Fields are not stored in yomitan order: score, sequence and term_tags were added later on, after
+// the definitions. The serialize implementation writes them in the right order.
 //
 // https://github.com/yomidevs/yomitan/blob/f271fc0da3e55a98fa91c9834d75fccc96deae27/ext/data/schemas/dictionary-term-bank-v3-schema.json
 //
 // https://github.com/MarvNC/yomichan-dict-builder/blob/master/src/types/yomitan/termbank.ts
 // @ TermInformation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct TermBank(
     pub String,                  // term
     pub String,                  // reading
     pub String,                  // definition_tags
     pub String,                  // rules
     pub Vec<DetailedDefinition>, // definitions
+    pub i32,                     // score (serialized at index 4)
+    pub i64,                     // sequence, -1 if not part of a sequence
+    pub String,                  // term_tags
 );
 
 impl Serialize for TermBank {
@@ -46,32 +48,104 @@ impl Serialize for TermBank {
         tup.serialize_element(&self.1)?;
         tup.serialize_element(&self.2)?;
         tup.serialize_element(&self.3)?;
-        tup.serialize_element(&0u8)?;
+        tup.serialize_element(&self.5)?;
         tup.serialize_element(&self.4)?;
-        tup.serialize_element(&0u8)?;
-        tup.serialize_element(&"")?;
+        tup.serialize_element(&self.6)?;
+        tup.serialize_element(&self.7)?;
         tup.end()
     }
 }
 
+impl<'de> Deserialize<'de> for TermBank {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // definition_tags can be null in other dictionaries
+        let (term, reading, definition_tags, rules, score, definitions, sequence, term_tags) =
+            <(
+                String,
+                String,
+                Option<String>,
+                String,
+                i32,
+                Vec<DetailedDefinition>,
+                i64,
+                String,
+            )>::deserialize(deserializer)?;
+        Ok(Self(
+            term,
+            reading,
+            definition_tags.unwrap_or_default(),
+            rules,
+            definitions,
+            score,
+            sequence,
+            term_tags,
+        ))
+    }
+}
+
 // There are other variants that we don't use at the moment.
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(untagged)]
 pub enum TermBankMeta {
     TermPhoneticTranscription(TermPhoneticTranscription),
+    TermFrequency(TermFrequency),
+    TermPitch(TermPitch),
 }
 
 // https://github.com/yomidevs/yomitan/blob/f271fc0da3e55a98fa91c9834d75fccc96deae27/ext/data/schemas/dictionary-term-meta-bank-v3-schema.json
 //
 // https://github.com/MarvNC/yomichan-dict-builder/blob/master/src/types/yomitan/termbankmeta.ts
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TermPhoneticTranscription(
     pub String,                // term
     pub String,                // static: "ipa"
     pub PhoneticTranscription, // phonetic transcription
 );
 
-#[derive(Debug, Serialize, Clone, PartialEq, Eq, Hash, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TermFrequency(
+    pub String,    // term
+    pub String,    // static: "freq"
+    pub Frequency, // frequency
+);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Frequency {
+    Value(u32),
+    WithReading { reading: String, frequency: u32 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TermPitch(
+    pub String,       // term
+    pub String,       // static: "pitch"
+    pub PitchAccents, // pitch accents
+);
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Default)]
+pub struct PitchAccents {
+    pub reading: String,
+    pub pitches: Vec<Pitch>,
+}
+
+// Mora positions are 0-indexed for nasal and devoice, while position is the number of moras
+// before the downstep (0 meaning heiban, i.e. no downstep).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Pitch {
+    pub position: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nasal: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devoice: Vec<usize>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<Tag>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Default)]
 pub struct PhoneticTranscription {
     pub reading: String,
     pub transcriptions: Vec<Ipa>,
@@ -87,13 +161,16 @@ pub struct Ipa {
 
 // https://github.com/MarvNC/yomichan-dict-builder/blob/master/src/types/yomitan/termbank.ts
 // @ StructuredContentNode
-#[derive(Debug, Serialize, Clone)]
+// Backlinks are read back as links: they only differ in that their content is static.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum Node {
     Text(String),              // 32
     Array(Vec<Node>),          // 32
     Generic(Box<GenericNode>), // 16
+    #[serde(skip_deserializing)]
     Backlink(BacklinkContent), // 40
+    Link(Box<LinkContent>),    // 8
 }
 
 impl Node {
@@ -115,7 +192,7 @@ impl Node {
     }
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct NodeData(Map<String, String>);
 
 impl<K, V> FromIterator<(K, V)> for NodeData
@@ -132,7 +209,7 @@ where
     }
 }
 
-#[derive(Debug, Serialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
 pub enum NTag {
     Span,
@@ -145,7 +222,7 @@ pub enum NTag {
 }
 
 // The order follows kty serialization, not yomichan builder order
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct GenericNode {
     pub tag: NTag,
 
@@ -164,7 +241,7 @@ impl GenericNode {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct BacklinkContent {
     href: String,
     content: &'static str,
@@ -174,6 +251,10 @@ impl BacklinkContent {
     pub const fn new(href: String, content: &'static str) -> Self {
         Self { href, content }
     }
+
+    pub const fn content(&self) -> &str {
+        self.content
+    }
 }
 
 // Custom Serialize to not have to store the constant 'a' tag
@@ -191,14 +272,67 @@ impl Serialize for BacklinkContent {
     }
 }
 
+/// Internal link: clicking it makes yomitan search for `href`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkContent {
+    href: String,
+    content: String,
+}
+
+impl LinkContent {
+    pub const fn new(href: String, content: String) -> Self {
+        Self { href, content }
+    }
+
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+}
+
+impl Serialize for LinkContent {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut state = serializer.serialize_struct("LinkContent", 3)?;
+        state.serialize_field("tag", "a")?;
+        state.serialize_field("href", &self.href)?;
+        state.serialize_field("content", &self.content)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for LinkContent {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Link {
+            tag: String,
+            href: String,
+            content: String,
+        }
+
+        let link = Link::deserialize(deserializer)?;
+        if link.tag != "a" {
+            return Err(de::Error::custom(format!("unsupported tag: {}", link.tag)));
+        }
+        Ok(Self::new(link.href, link.content))
+    }
+}
+
 // https://github.com/MarvNC/yomichan-dict-builder/blob/master/src/types/yomitan/termbank.ts
 // @ DetailedDefinition
-#[derive(Debug, Serialize, Clone)]
+//
+// Inflection goes before StructuredContent since serde would read an inflection array as a struct.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 #[serde(untagged)]
 pub enum DetailedDefinition {
     Text(String),
-    StructuredContent(StructuredContent),
     Inflection((String, Vec<String>)),
+    StructuredContent(StructuredContent),
 }
 
 impl DetailedDefinition {
@@ -210,13 +344,19 @@ impl DetailedDefinition {
     }
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct StructuredContent {
     #[serde(rename = "type")]
     ty: String, // should be hardcoded to "structured-content" (but then to serialize it...)
     content: Node,
 }
 
+impl StructuredContent {
+    pub const fn content(&self) -> &Node {
+        &self.content
+    }
+}
+
 pub fn wrap(tag: NTag, content_ty: &str, content: Node) -> Node {
     GenericNode {
         tag,
@@ -249,7 +389,7 @@ pub fn wrap(tag: NTag, content_ty: &str, content: Node) -> Node {
 // The actual yomitan type.
 //
 // https://github.com/MarvNC/yomichan-dict-builder/blob/master/src/types/yomitan/tagbank.ts
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct TagInformation {
     pub short_tag: String, // tagName
     pub category: String,
@@ -286,3 +426,20 @@ impl Serialize for TagInformation {
         tup.end()
     }
 }
+
+impl<'de> Deserialize<'de> for TagInformation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (short_tag, category, sort_order, long_tag, popularity_score) =
+            <(String, String, i32, String, i32)>::deserialize(deserializer)?;
+        Ok(Self {
+            short_tag,
+            category,
+            sort_order,
+            long_tag,
+            popularity_score,
+        })
+    }
+}
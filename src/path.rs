@@ -4,6 +4,7 @@ use std::path::PathBuf;
 
 use crate::{
     cli::{Langs, SimpleArgs},
+    config::{DictConfig, render},
     lang::{Edition, EditionLang, Lang},
 };
 
@@ -15,6 +16,8 @@ pub enum DictionaryType {
     GlossaryExtended,
     Ipa,
     IpaMerged,
+    Pitch,
+    Freq,
 }
 
 /// Used only for the temporary files folder (`dir_temp`).
@@ -26,6 +29,8 @@ impl fmt::Display for DictionaryType {
             Self::GlossaryExtended => write!(f, "glossary-ext"),
             Self::Ipa => write!(f, "ipa"),
             Self::IpaMerged => write!(f, "ipa-merged"),
+            Self::Pitch => write!(f, "pitch"),
+            Self::Freq => write!(f, "freq"),
         }
     }
 }
@@ -46,6 +51,9 @@ pub struct PathManager {
     root_dir: PathBuf,
     save_temps: bool,
     experimental: bool,
+
+    /// Templates from the config file (`--config`), if any.
+    config: DictConfig,
 }
 
 impl PathManager {
@@ -59,9 +67,38 @@ impl PathManager {
             root_dir: args.options().root_dir.clone(),
             save_temps: args.options().save_temps,
             experimental: args.options().experimental,
+            config: args
+                .options()
+                .config
+                .as_ref()
+                .map(|config| config.section(&dict_ty.to_string()))
+                .unwrap_or_default(),
         }
     }
 
+    pub const fn config(&self) -> &DictConfig {
+        &self.config
+    }
+
+    /// Fill the placeholders of a config template.
+    pub fn render(&self, template: &str) -> String {
+        render(
+            template,
+            &[
+                ("dict_name", &self.dict_name),
+                ("edition", self.edition.as_ref()),
+                ("source", self.source.as_ref()),
+                ("target", self.target.as_ref()),
+                ("type", &self.dict_ty.to_string()),
+                ("name", &self.dict_name_expanded()),
+            ],
+        )
+    }
+
+    pub const fn dict_ty(&self) -> DictionaryType {
+        self.dict_ty
+    }
+
     // Seems a bit hacky to get it from the PathManager...
     pub const fn langs(&self) -> (Edition, Lang, Lang) {
         (self.edition, self.source, self.target)
@@ -78,7 +115,13 @@ impl PathManager {
         self.root_dir.join("dict")
     }
     /// Example: `data/dict/el/el`
+    ///
+    /// Can be changed with the `output_dir` config template.
     fn dir_dict(&self) -> PathBuf {
+        if let Some(output_dir) = &self.config.output_dir {
+            return self.root_dir.join(self.render(output_dir));
+        }
+
         self.dir_dicts().join(match self.dict_ty {
             // For merged dictionaries, use the edition (displays as "all")
             // TODO: this should be the opposite
@@ -156,6 +199,7 @@ impl PathManager {
     /// Note that the English downloads are already filtered. That is:
     /// * [`data/kaikki/zh-en-extract.jsonl`]
     ///   is guaranteed to only have Chinese words with glosses in English
+    ///
     /// but for:
     /// * [`data/kaikki/en-zh-extract.jsonl`]
     ///   there is no such guarantee (it is an alias, *not* downloaded)
@@ -171,7 +215,7 @@ impl PathManager {
                 .map(|edl| (edl, self.aliases(edl, edl.into())))
                 .collect(),
             // One edition, other_lang is used when filtering
-            Main | Ipa => {
+            Main | Ipa | Pitch | Freq => {
                 let edl = edition.try_into().unwrap();
                 vec![(edl, self.aliases(edl, source))]
             }
@@ -229,6 +273,12 @@ impl PathManager {
                 format!("{}-{}-{}-ipa", self.dict_name, self.source, self.target)
             }
             DictionaryType::IpaMerged => format!("{}-{}-ipa", self.dict_name, self.target),
+            DictionaryType::Pitch => {
+                format!("{}-{}-{}-pitch", self.dict_name, self.source, self.target)
+            }
+            DictionaryType::Freq => {
+                format!("{}-{}-{}-freq", self.dict_name, self.source, self.target)
+            }
         };
 
         if self.experimental {
@@ -238,19 +288,37 @@ impl PathManager {
         expanded
     }
 
+    /// The zip file name, without extension.
+    ///
+    /// Defaults to [`Self::dict_name_expanded`], can be changed with the `zip_name` config template.
+    pub fn zip_name(&self) -> String {
+        match &self.config.zip_name {
+            Some(zip_name) => self.render(zip_name),
+            None => self.dict_name_expanded(),
+        }
+    }
+
     /// Depends on the dictionary type (main, glossary etc.)
     ///
     /// Example: `data/dict/el/en/dictionary_name-el-en.zip`
     /// Example: `data/dict/el/en/dictionary_name-el-en-gloss.zip`
+    ///
+    /// Can be changed with the `zip_name` config template.
     pub fn path_dict(&self) -> PathBuf {
-        self.dir_dict()
-            .join(format!("{}.zip", self.dict_name_expanded()))
+        self.dir_dict().join(format!("{}.zip", self.zip_name()))
     }
 
     /// Example: `data/dict/el/el/temp/diagnostics`
     pub fn dir_diagnostics(&self) -> PathBuf {
         self.dir_temp().join("diagnostics")
     }
+
+    /// Cache of `--incremental`, one per edition read.
+    ///
+    /// Example: `data/dict/el/el/temp/cache/el.tsv`
+    pub fn path_cache(&self, edition: EditionLang) -> PathBuf {
+        self.dir_temp().join("cache").join(format!("{edition}.tsv"))
+    }
 }
 
 #[cfg(test)]
@@ -259,6 +327,7 @@ mod tests {
         GlossaryArgs, GlossaryExtendedArgs, GlossaryExtendedLangs, GlossaryLangs, MainArgs,
         MainLangs,
     };
+    use crate::config::Config;
 
     use super::*;
 
@@ -343,6 +412,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn paths_config() {
+        let config: Config = toml::from_str(
+            r#"
+            [default]
+            output_dir = "out/{target}/{source}"
+
+            [main]
+            zip_name = "{dict_name}-{source}-{target}-{type}"
+            "#,
+        )
+        .unwrap();
+        let mut args = MainArgs {
+            langs: MainLangs {
+                edition: EditionLang::En,
+                source: Lang::De,
+                target: EditionLang::En,
+            },
+            dict_name: "kty".to_string(),
+            ..Default::default()
+        };
+        args.options.config = Some(config);
+
+        let pm = PathManager::new(DictionaryType::Main, &args);
+        assert_eq!(
+            pm.path_dict(),
+            PathBuf::from("out/en/de/kty-de-en-main.zip")
+        );
+
+        let pm = PathManager::new(DictionaryType::Ipa, &args);
+        assert_eq!(pm.path_dict(), PathBuf::from("out/en/de/kty-de-en-ipa.zip"));
+    }
+
     #[test]
     fn paths_glossary_extended() {
         let args = GlossaryExtendedArgs {
@@ -1,10 +1,14 @@
 pub mod tags_constants;
 
 use std::cmp::Ordering;
+use std::sync::LazyLock;
 
 use indexmap::IndexMap;
+use serde::Deserialize;
 use tags_constants::{POSES, TAG_BANK, TAG_ORDER};
 
+use crate::Map;
+use crate::lang::Lang;
 use crate::models::kaikki::Tag;
 use crate::models::yomitan::TagInformation;
 
@@ -201,22 +205,101 @@ pub fn merge_person_tags(tags: &mut Vec<Tag>) {
     }
 }
 
-/// Return a Vec<TagInformation> from `tag_bank_terms` that fits the yomitan tag schema.
-pub fn get_tag_bank_as_tag_info() -> Vec<TagInformation> {
-    TAG_BANK.iter().map(TagInformation::new).collect()
+/// Translation of a tag bank entry. Missing fields are kept in English.
+#[derive(Debug, Deserialize)]
+struct TagTranslation {
+    short: Option<String>,
+    long: Option<String>,
 }
 
-/// Look for the tag in `TAG_BANK` (`tag_bank_terms.json`) and return the `TagInformation` if any.
+/// Translations keyed by the normalized English long tag.
+type TagBankLocale = Map<String, TagTranslation>;
+
+const TAG_BANK_LOCALES: [(Lang, &str); 3] = [
+    (Lang::De, include_str!("../../assets/tag_banks/de.json")),
+    (Lang::Es, include_str!("../../assets/tag_banks/es.json")),
+    (Lang::Fr, include_str!("../../assets/tag_banks/fr.json")),
+];
+
+struct ParsedTagBankLocale {
+    translations: TagBankLocale,
+    /// Index in `TAG_BANK` of every translated long tag, to find translated tags. Some tags share
+    /// a translation (ex. figurative and figuratively), in which case the first one is kept.
+    by_translated_long: Map<String, usize>,
+}
+
+static PARSED_TAG_BANK_LOCALES: LazyLock<Map<Lang, ParsedTagBankLocale>> = LazyLock::new(|| {
+    TAG_BANK_LOCALES
+        .into_iter()
+        .map(|(lang, json)| {
+            let translations: TagBankLocale =
+                serde_json::from_str(json).expect("bundled tag bank locales are valid");
+            let mut by_translated_long = Map::default();
+            for (idx, entry) in TAG_BANK.iter().enumerate() {
+                if let Some(long) = translations.get(entry.3[0]).and_then(|t| t.long.clone()) {
+                    by_translated_long.entry(long).or_insert(idx);
+                }
+            }
+            let locale = ParsedTagBankLocale {
+                translations,
+                by_translated_long,
+            };
+            (lang, locale)
+        })
+        .collect()
+});
+
+/// Translate the `TagInformation` to the target language, if there is a translation.
 ///
-/// Note that `long_tag` is returned normalized.
-pub fn find_tag_in_bank(tag: &str) -> Option<TagInformation> {
-    TAG_BANK.iter().find_map(|entry| {
-        if entry.3.contains(&tag) {
-            Some(TagInformation::new(entry))
-        } else {
-            None
-        }
-    })
+/// The short tags of parts of speech are never translated since they double as deinflection
+/// rule identifiers.
+fn localize_tag_info(mut tag_info: TagInformation, target: Lang) -> TagInformation {
+    let Some(translation) = PARSED_TAG_BANK_LOCALES
+        .get(&target)
+        .and_then(|locale| locale.translations.get(&tag_info.long_tag))
+    else {
+        return tag_info;
+    };
+
+    if let Some(short) = &translation.short
+        && tag_info.category != "partOfSpeech"
+    {
+        tag_info.short_tag.clone_from(short);
+    }
+    if let Some(long) = &translation.long {
+        tag_info.long_tag.clone_from(long);
+    }
+    tag_info
+}
+
+/// Return a Vec<TagInformation> from `tag_bank_terms` that fits the yomitan tag schema, translated
+/// to the target language.
+pub fn get_tag_bank_as_tag_info(target: Lang) -> Vec<TagInformation> {
+    TAG_BANK
+        .iter()
+        .map(|entry| localize_tag_info(TagInformation::new(entry), target))
+        .collect()
+}
+
+/// Look for the tag in `TAG_BANK` (`tag_bank_terms.json`) and return the `TagInformation`,
+/// translated to the target language, if any.
+///
+/// The tag can either be an English long tag, or a translated one.
+///
+/// Note that `long_tag` is returned normalized, that is, the first English long tag of the entry,
+/// or its translation to the target language if there is one.
+pub fn find_tag_in_bank(tag: &str, target: Lang) -> Option<TagInformation> {
+    let entry = TAG_BANK
+        .iter()
+        .find(|entry| entry.3.contains(&tag))
+        .or_else(|| {
+            let locale = PARSED_TAG_BANK_LOCALES.get(&target)?;
+            locale
+                .by_translated_long
+                .get(tag)
+                .map(|&idx| &TAG_BANK[idx])
+        })?;
+    Some(localize_tag_info(TagInformation::new(entry), target))
 }
 
 /// Look for the short form in POSES (`tag_bank_terms.json` with category "partOfSpeech") and
@@ -231,6 +314,64 @@ pub fn find_short_pos(pos: &str) -> Option<&'static str> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn tag_bank_locales() {
+        for (lang, _) in TAG_BANK_LOCALES {
+            let locale = &PARSED_TAG_BANK_LOCALES[&lang];
+            for long_tag in locale.translations.keys() {
+                assert!(
+                    TAG_BANK.iter().any(|entry| entry.3[0] == long_tag),
+                    "{lang}: '{long_tag}' is not in the tag bank"
+                );
+            }
+
+            // Translated tags are found by their long tag, first entry first
+            for (long, &idx) in &locale.by_translated_long {
+                let first = TAG_BANK.iter().position(|entry| {
+                    locale
+                        .translations
+                        .get(entry.3[0])
+                        .and_then(|t| t.long.as_ref())
+                        == Some(long)
+                });
+                assert_eq!(first, Some(idx), "{lang}: '{long}'");
+            }
+
+            // Yomitan identifies tags by their short tag: translations can not merge tags
+            let mut english_short_tags: Map<String, &str> = Map::default();
+            for (entry, tag_info) in TAG_BANK.iter().zip(get_tag_bank_as_tag_info(lang)) {
+                let english_short_tag = english_short_tags
+                    .entry(tag_info.short_tag.clone())
+                    .or_insert(entry.0);
+                assert_eq!(
+                    *english_short_tag, entry.0,
+                    "{lang}: '{}' translates two tags",
+                    tag_info.short_tag
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn find_localized_tag() {
+        let masculine = find_tag_in_bank("masculine", Lang::De).unwrap();
+        assert_eq!(masculine.short_tag, "mask.");
+        assert_eq!(masculine.long_tag, "Maskulinum");
+        assert_eq!(
+            find_tag_in_bank("Maskulinum", Lang::De).unwrap().short_tag,
+            "mask."
+        );
+        assert_eq!(
+            find_tag_in_bank("masculine", Lang::En).unwrap().short_tag,
+            "masc"
+        );
+
+        // Parts of speech keep their short tag
+        let noun = find_tag_in_bank("noun", Lang::De).unwrap();
+        assert_eq!(noun.short_tag, "n");
+        assert_eq!(noun.long_tag, "Substantiv");
+    }
+
     fn to_string_vec(str_vec: &[&str]) -> Vec<String> {
         str_vec.iter().map(|s| (*s).to_string()).collect()
     }
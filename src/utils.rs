@@ -1,13 +1,42 @@
-use anyhow::Result;
-use std::fs;
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::Read;
 use std::path::Path;
+use zip::ZipArchive;
 
 use crate::lang::{EditionLang, Lang};
 
 pub const SKIP_C: &str = "⏭";
 pub const CHECK_C: &str = "✓";
 
-fn size(path: &Path) -> std::io::Result<u64> {
+/// 64-bit FNV-1a hasher.
+///
+/// Unlike std's `DefaultHasher`, that may change between releases, it always gives the same hash,
+/// so that hashes can be persisted. Only feed it bytes with `write`: the `Hash` implementations of
+/// std types are not stable either.
+pub struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+pub fn size(path: &Path) -> std::io::Result<u64> {
     let md = fs::metadata(path)?;
     if md.is_file() {
         Ok(md.len())
@@ -23,7 +52,7 @@ fn size(path: &Path) -> std::io::Result<u64> {
     }
 }
 
-fn human_size(size_bytes: f64) -> String {
+pub fn human_size(size_bytes: f64) -> String {
     let mut size = size_bytes;
     for unit in ["B", "KB", "MB"] {
         if size < 1024.0 {
@@ -58,6 +87,39 @@ pub fn pretty_print_at_path(msg: &str, path: &Path) {
     print!("{}", pretty_msg_at_path(msg, path));
 }
 
+/// Call `f` with the name and content of every file of a dictionary, either zipped or a directory
+/// (ex. the temporary files of `--save-temps`).
+pub fn for_each_dictionary_file(
+    path: &Path,
+    mut f: impl FnMut(&str, &[u8]) -> Result<()>,
+) -> Result<()> {
+    if path.is_dir() {
+        let mut paths: Vec<_> = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()?;
+        paths.sort();
+        for path in paths {
+            if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
+                f(file_name, &fs::read(&path)?)?;
+            }
+        }
+    } else {
+        let file =
+            File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+        let mut zip = ZipArchive::new(file)?;
+        let mut bytes = Vec::new();
+        for idx in 0..zip.len() {
+            let mut file = zip.by_index(idx)?;
+            let file_name = file.name().to_string();
+            bytes.clear();
+            file.read_to_end(&mut bytes)?;
+            f(&file_name, &bytes)?;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn skip_because_file_exists(skipped: &str, path: &Path) {
     let msg = format!("{SKIP_C} Skipping {skipped}: file already exists");
     pretty_println_at_path(&msg, path);
@@ -73,6 +135,17 @@ pub fn link_wiktionary(edition: EditionLang, source: Lang, word: &str) -> String
     )
 }
 
+/// Return an internal yomitan link that searches for this word.
+pub fn link_yomitan_search(word: &str) -> String {
+    // Only escape what would break the query string
+    let query = word
+        .replace('%', "%25")
+        .replace('&', "%26")
+        .replace('+', "%2B")
+        .replace('#', "%23");
+    format!("?query={query}&wildcards=off")
+}
+
 /// Return a link to the kaikki page of this word.
 pub fn link_kaikki(edition: EditionLang, source: Lang, word: &str) -> String {
     // 楽しい >> 楽/楽し/楽しい
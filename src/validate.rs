@@ -0,0 +1,257 @@
+//! Validation of yomitan dictionaries against the official JSON schemas.
+//!
+//! The schemas are vendored from:
+//! <https://github.com/yomidevs/yomitan/tree/master/ext/data/schemas>
+
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result};
+use jsonschema::Validator;
+use serde_json::Value;
+use zip::ZipArchive;
+
+use crate::utils::for_each_dictionary_file;
+
+const INDEX_SCHEMA: &str = include_str!("../assets/schemas/dictionary-index-schema.json");
+const TAG_BANK_SCHEMA: &str = include_str!("../assets/schemas/dictionary-tag-bank-v3-schema.json");
+const TERM_BANK_SCHEMA: &str =
+    include_str!("../assets/schemas/dictionary-term-bank-v3-schema.json");
+const TERM_META_BANK_SCHEMA: &str =
+    include_str!("../assets/schemas/dictionary-term-meta-bank-v3-schema.json");
+
+fn compile(schema: &str) -> Validator {
+    let schema: Value = serde_json::from_str(schema).expect("vendored schemas are valid json");
+    jsonschema::validator_for(&schema).expect("vendored schemas are valid schemas")
+}
+
+static INDEX_VALIDATOR: LazyLock<Validator> = LazyLock::new(|| compile(INDEX_SCHEMA));
+static TAG_BANK_VALIDATOR: LazyLock<Validator> = LazyLock::new(|| compile(TAG_BANK_SCHEMA));
+static TERM_BANK_VALIDATOR: LazyLock<Validator> = LazyLock::new(|| compile(TERM_BANK_SCHEMA));
+static TERM_META_BANK_VALIDATOR: LazyLock<Validator> =
+    LazyLock::new(|| compile(TERM_META_BANK_SCHEMA));
+
+/// The validator for a file of the dictionary, if it is one of the json files we know about.
+fn validator_for_file(file_name: &str) -> Option<&'static Validator> {
+    let is_bank = |prefix: &str| {
+        file_name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_suffix(".json"))
+            .is_some_and(|num| num.parse::<usize>().is_ok())
+    };
+
+    if file_name == "index.json" {
+        Some(&INDEX_VALIDATOR)
+    } else if is_bank("tag_bank_") {
+        Some(&TAG_BANK_VALIDATOR)
+    } else if is_bank("term_bank_") {
+        Some(&TERM_BANK_VALIDATOR)
+    } else if is_bank("term_meta_bank_") {
+        Some(&TERM_META_BANK_VALIDATOR)
+    } else {
+        None
+    }
+}
+
+/// A place where a dictionary file does not conform to its schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Ex. `term_bank_1.json`
+    pub file: String,
+    /// Index of the offending entry, for banks.
+    pub entry: Option<usize>,
+    /// JSON pointer to the offending value, ex. `/12/5/0/content`.
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.file)?;
+        if let Some(entry) = self.entry {
+            write!(f, " (entry {entry})")?;
+        }
+        let path = if self.path.is_empty() {
+            "/"
+        } else {
+            &self.path
+        };
+        write!(f, " at {path}: {}", self.message)
+    }
+}
+
+/// Validate the content of a dictionary file. Files without a schema (ex. `styles.css`) are
+/// always valid.
+pub fn validate_json(file_name: &str, json: &Value) -> Vec<Violation> {
+    let Some(validator) = validator_for_file(file_name) else {
+        return Vec::new();
+    };
+    let is_bank = file_name != "index.json";
+
+    validator
+        .iter_errors(json)
+        .map(|err| {
+            let path = err.instance_path().to_string();
+            let entry = if is_bank {
+                path.split('/').nth(1).and_then(|idx| idx.parse().ok())
+            } else {
+                None
+            };
+            Violation {
+                file: file_name.to_string(),
+                entry,
+                path,
+                message: err.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn validate_bytes(file_name: &str, bytes: &[u8]) -> Result<Vec<Violation>> {
+    if validator_for_file(file_name).is_none() {
+        return Ok(Vec::new());
+    }
+    let json: Value =
+        serde_json::from_slice(bytes).with_context(|| format!("{file_name} is not valid json"))?;
+    Ok(validate_json(file_name, &json))
+}
+
+/// Validate every file of a zipped dictionary.
+pub fn validate_zip<R: Read + Seek>(reader: R) -> Result<Vec<Violation>> {
+    let mut zip = ZipArchive::new(reader)?;
+    let mut violations = Vec::new();
+    let mut bytes = Vec::new();
+
+    for idx in 0..zip.len() {
+        let mut file = zip.by_index(idx)?;
+        let file_name = file.name().to_string();
+        bytes.clear();
+        file.read_to_end(&mut bytes)?;
+        violations.extend(validate_bytes(&file_name, &bytes)?);
+    }
+
+    let has_index = zip.file_names().any(|name| name == "index.json");
+    check_index(has_index, &mut violations);
+
+    Ok(violations)
+}
+
+/// Validate every file of an unzipped dictionary, ex. the temporary files of `--save-temps`.
+pub fn validate_dir(dir: &Path) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+    let mut has_index = false;
+    for_each_dictionary_file(dir, |file_name, bytes| {
+        has_index |= file_name == "index.json";
+        violations.extend(validate_bytes(file_name, bytes)?);
+        Ok(())
+    })?;
+    check_index(has_index, &mut violations);
+    Ok(violations)
+}
+
+/// Yomitan can not import a dictionary without an index.
+fn check_index(has_index: bool, violations: &mut Vec<Violation>) {
+    if !has_index {
+        violations.push(Violation {
+            file: "index.json".to_string(),
+            entry: None,
+            path: String::new(),
+            message: "missing file".to_string(),
+        });
+    }
+}
+
+/// Validate a dictionary, either zipped or a directory.
+pub fn validate_path(path: &Path) -> Result<Vec<Violation>> {
+    if path.is_dir() {
+        validate_dir(path)
+    } else {
+        let file =
+            File::open(path).with_context(|| format!("could not open {}", path.display()))?;
+        validate_zip(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+    use std::fs;
+
+    #[test]
+    fn valid_term_bank() {
+        let bank = json!([
+            ["word", "", "n", "", 0, ["gloss", ["lemma", ["plural"]]], 1, ""],
+            [
+                "word",
+                "",
+                "",
+                "",
+                0,
+                [{
+                    "type": "structured-content",
+                    "content": [{ "tag": "a", "href": "?query=word", "content": "word" }]
+                }],
+                1,
+                ""
+            ]
+        ]);
+        assert_eq!(validate_json("term_bank_1.json", &bank), Vec::new());
+    }
+
+    #[test]
+    fn invalid_term_bank() {
+        let bank = json!([
+            ["word", "", "", "", 0, ["gloss"], 1, ""],
+            ["word", "", "", "", 0, [{ "type": "structured-content", "content": { "tag": "p" } }], 1, ""]
+        ]);
+        let violations = validate_json("term_bank_3.json", &bank);
+        assert!(!violations.is_empty());
+        assert!(
+            violations
+                .iter()
+                .all(|violation| violation.entry == Some(1))
+        );
+        assert!(
+            violations[0]
+                .to_string()
+                .starts_with("term_bank_3.json (entry 1) at /1")
+        );
+    }
+
+    #[test]
+    fn invalid_index() {
+        let index = json!({ "title": "kty", "format": 3, "isUpdatable": true });
+        let violations = validate_json("index.json", &index);
+        assert!(!violations.is_empty());
+        assert!(violations.iter().all(|violation| violation.entry.is_none()));
+    }
+
+    #[test]
+    fn missing_index() {
+        let dir = std::env::temp_dir().join(format!("kty-validate-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        fs::write(dir.join("term_bank_1.json"), "[]").unwrap();
+        let violations = validate_dir(&dir).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].file, "index.json");
+
+        let index = json!({ "title": "kty", "revision": "2024.01.01", "format": 3 });
+        fs::write(dir.join("index.json"), index.to_string()).unwrap();
+        assert_eq!(validate_dir(&dir).unwrap(), Vec::new());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unknown_files_are_valid() {
+        assert_eq!(validate_json("styles.css", &json!(null)), Vec::new());
+        assert_eq!(validate_json("term_bank_x.json", &json!(null)), Vec::new());
+    }
+}
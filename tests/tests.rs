@@ -1,8 +1,15 @@
-use kty::cli::{GlossaryArgs, GlossaryLangs, MainArgs, MainLangs, Options};
-use kty::dict::{DGlossary, DIpa, DMain};
-use kty::lang::{EditionLang, Lang};
+use kty::cli::{
+    GlossaryArgs, GlossaryLangs, IpaMergedArgs, IpaMergedLangs, MainArgs, MainLangs, Options,
+};
+use kty::dict::{
+    BatchItem, DFreq, DGlossary, DIpa, DIpaMerged, DMain, DPitch, Dictionary, make_dicts,
+};
+use kty::freq::{FreqList, FreqMode};
+use kty::lang::{Edition, EditionLang, Lang};
 use kty::make_dict;
+use kty::models::kaikki::LinkageKind;
 use kty::path::{DictionaryType, PathManager};
+use kty::validate::validate_dir;
 
 use anyhow::{Ok, Result};
 use tracing_subscriber::EnvFilter;
@@ -10,6 +17,7 @@ use tracing_subscriber::fmt::format::FmtSpan;
 
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 /// Clean empty folders under folder "root" recursively.
 fn cleanup(root: &Path) -> bool {
@@ -147,7 +155,7 @@ fn snapshot() {
         let args = fixture_main_args(target, *source, target, &fixture_dir);
         let pm = PathManager::new(DictionaryType::Main, &args);
 
-        if let Err(e) = shapshot_main(&args.options, &pm) {
+        if let Err(e) = check_snapshot(DMain, &args.options, &pm) {
             panic!("({source}): {e}");
         }
     }
@@ -166,6 +174,7 @@ fn snapshot() {
             let args = fixture_glossary_args(source, source, *possible_target, &fixture_dir);
             let pm = PathManager::new(DictionaryType::Glossary, &args);
             make_dict(DGlossary, &args.options, &pm).unwrap();
+            check_schemas(&pm).unwrap();
         }
     }
 
@@ -177,11 +186,204 @@ fn snapshot() {
         let args = fixture_main_args(target, *source, target, &fixture_dir);
         let pm = PathManager::new(DictionaryType::Ipa, &args);
         make_dict(DIpa, &args.options, &pm).unwrap();
+        check_schemas(&pm).unwrap();
+    }
+
+    // pitch
+    for (source, target) in &cases {
+        let Result::Ok(target) = EditionLang::try_from(*target) else {
+            continue; // skip if target is not edition
+        };
+        let args = fixture_main_args(target, *source, target, &fixture_dir);
+        let pm = PathManager::new(DictionaryType::Pitch, &args);
+        make_dict(DPitch, &args.options, &pm).unwrap();
+        check_schemas(&pm).unwrap();
+    }
+
+    // freq, only for the cases with a frequency list
+    for (source, target) in &cases {
+        let path_freq = fixture_dir
+            .join("freq")
+            .join(format!("{source}-{target}.tsv"));
+        if !path_freq.exists() {
+            continue;
+        }
+        let Result::Ok(target) = EditionLang::try_from(*target) else {
+            continue; // skip if target is not edition
+        };
+        let mut args = fixture_main_args(target, *source, target, &fixture_dir);
+        let freq_list = FreqList::load(&path_freq, FreqMode::Count).unwrap();
+        args.options.freq_list = Some(Arc::new(freq_list));
+        let pm = PathManager::new(DictionaryType::Freq, &args);
+        if let Err(e) = check_snapshot(DFreq, &args.options, &pm) {
+            panic!("({source}): {e}");
+        }
+    }
+
+    // main, with options that are off above, each in its own output directory
+    type SetOption = fn(&mut Options);
+    let option_cases: [(&str, EditionLang, Lang, SetOption); 2] = [
+        ("linkages", EditionLang::En, Lang::De, |options| {
+            options.linkages = LinkageKind::ALL.to_vec();
+        }),
+        ("notes", EditionLang::De, Lang::De, |options| {
+            options.notes = true
+        }),
+    ];
+    for (name, target, source, set_option) in option_cases {
+        let mut args = fixture_main_args(target, source, target, &fixture_dir);
+        set_option(&mut args.options);
+        let output_dir = format!("[default]\noutput_dir = 'dict/{{source}}/{{target}}/{name}'");
+        args.options.config = Some(toml::from_str(&output_dir).unwrap());
+        let pm = PathManager::new(DictionaryType::Main, &args);
+        if let Err(e) = check_snapshot(DMain, &args.options, &pm) {
+            panic!("({name}): {e}");
+        }
     }
 
     cleanup(&fixture_dir.join("dict"));
 }
 
+/// A batch gives the same dictionaries as making them one by one
+#[test]
+fn batch() {
+    let root = std::env::temp_dir().join(format!("kty-batch-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    // The jsonlines are looked up in the root directory, since batches do not support --input
+    let setup = |name: &str| {
+        let root = root.join(name);
+        let main_args = fixture_main_args(EditionLang::En, Lang::De, EditionLang::En, &root);
+        let glossary_args =
+            fixture_glossary_args(EditionLang::De, EditionLang::De, Lang::En, &root);
+        // The Russian main dictionary reads the Russian edition, which is also the last edition
+        // read by the merged ipa dictionary: they share it, but the latter reads English first.
+        let main_ru_args = fixture_main_args(EditionLang::Ru, Lang::Ru, EditionLang::Ru, &root);
+        let ipa_merged_args = IpaMergedArgs {
+            langs: IpaMergedLangs {
+                edition: Edition::All,
+                source: Lang::Ru,
+                target: Lang::Ru,
+            },
+            options: fixture_options(&root),
+            ..Default::default()
+        };
+        let pms = [
+            PathManager::new(DictionaryType::Main, &main_args),
+            PathManager::new(DictionaryType::Ipa, &main_args),
+            PathManager::new(DictionaryType::Glossary, &glossary_args),
+            PathManager::new(DictionaryType::Main, &main_ru_args),
+            PathManager::new(DictionaryType::IpaMerged, &ipa_merged_args),
+        ];
+        for pm in &pms {
+            for (edition, paths) in pm.paths_jsonl() {
+                let path = &paths[0];
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                let fixture = match (pm.dict_ty(), edition) {
+                    // Both have a word in common: снег
+                    (DictionaryType::IpaMerged, EditionLang::En) => "ru-en-extract.jsonl",
+                    (DictionaryType::IpaMerged, EditionLang::Ru) => "ru-ru-extract.jsonl",
+                    // Editions without fixtures are empty, unless another dictionary reads them
+                    (DictionaryType::IpaMerged, _) => {
+                        if !path.exists() {
+                            fs::write(path, "").unwrap();
+                        }
+                        continue;
+                    }
+                    _ => path.file_name().unwrap().to_str().unwrap(),
+                };
+                fs::copy(Path::new("tests/kaikki").join(fixture), path).unwrap();
+            }
+        }
+        let mut options = main_args.options;
+        options.quiet = true;
+        (pms, options)
+    };
+
+    let ([main, ipa, glossary, main_ru, ipa_merged], options) = setup("single");
+    make_dict(DMain, &options, &main).unwrap();
+    make_dict(DIpa, &options, &ipa).unwrap();
+    make_dict(DGlossary, &options, &glossary).unwrap();
+    make_dict(DMain, &options, &main_ru).unwrap();
+    make_dict(DIpaMerged, &options, &ipa_merged).unwrap();
+    let singles =
+        [main, ipa, glossary, main_ru, ipa_merged].map(|pm| read_dir_sorted(&pm.dir_temp_dict()));
+
+    let ([main, ipa, glossary, main_ru, ipa_merged], options) = setup("batch");
+    let dirs = [&main, &ipa, &glossary, &main_ru, &ipa_merged].map(PathManager::dir_temp_dict);
+    let dicts = vec![
+        BatchItem::boxed(DMain, &options, main),
+        BatchItem::boxed(DIpa, &options, ipa),
+        BatchItem::boxed(DGlossary, &options, glossary),
+        BatchItem::boxed(DMain, &options, main_ru),
+        BatchItem::boxed(DIpaMerged, &options, ipa_merged),
+    ];
+    make_dicts(dicts, &options).unwrap();
+    let batched = dirs.map(|dir| read_dir_sorted(&dir));
+
+    for (single, batched) in singles.iter().zip(&batched) {
+        assert!(!single.is_empty());
+        assert_eq!(single, batched);
+    }
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+/// An incremental build gives the same dictionary as a full one, whatever was in the cache
+#[test]
+fn incremental() {
+    let fixture_input = PathBuf::from("tests/kaikki/de-en-extract.jsonl");
+    let content = fs::read_to_string(fixture_input).unwrap();
+    let lines: Vec<_> = content.lines().collect();
+
+    let root = std::env::temp_dir().join(format!("kty-incremental-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+
+    let build = |name: &str, lines: &[&str], incremental: bool| {
+        let root = root.join(name);
+        fs::create_dir_all(&root).unwrap();
+        let input = root.join("input.jsonl");
+        fs::write(&input, lines.join("\n")).unwrap();
+
+        let mut args = fixture_main_args(EditionLang::En, Lang::De, EditionLang::En, &root);
+        args.options.input = Some(input);
+        args.options.incremental = incremental;
+        args.options.quiet = true;
+        let pm = PathManager::new(DictionaryType::Main, &args);
+        make_dict(DMain, &args.options, &pm).unwrap();
+        read_dir_sorted(&pm.dir_temp_dict())
+    };
+
+    let full = build("full", &lines, false);
+    assert_eq!(build("incremental", &lines, true), full, "empty cache");
+    assert_eq!(build("incremental", &lines, true), full, "full cache");
+
+    // Remove a line, change another, and repeat the last one
+    let changed = lines[1].replacen("\"word\": \"", "\"word\": \"x", 1);
+    let mut churned = lines[2..].to_vec();
+    churned.insert(0, &changed);
+    churned.push(lines[lines.len() - 1]);
+    let full = build("full-churned", &churned, false);
+    assert_eq!(build("incremental", &churned, true), full, "churned");
+
+    fs::remove_dir_all(&root).unwrap();
+}
+
+/// The name and content of every file in a directory, but the index, that holds the date
+fn read_dir_sorted(dir: &Path) -> Vec<(PathBuf, String)> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| !path.ends_with("index.json"))
+        .map(|path| {
+            let content = fs::read_to_string(&path).unwrap();
+            (PathBuf::from(path.file_name().unwrap()), content)
+        })
+        .collect();
+    files.sort();
+    files
+}
+
 /// Delete generated artifacts from previous tests runs, if any
 fn delete_previous_output(pm: &PathManager) -> Result<()> {
     let pathdir_dict_temp = pm.dir_temp_dict();
@@ -212,10 +414,27 @@ fn check_git_diff(pm: &PathManager) -> Result<()> {
     Ok(())
 }
 
+/// Check the generated banks against the yomitan schemas, if there are any
+fn check_schemas(pm: &PathManager) -> Result<()> {
+    let pathdir_dict_temp = pm.dir_temp_dict();
+    // The directory is created beforehand, but stays empty if no entry was found
+    if !pathdir_dict_temp.exists() || fs::read_dir(&pathdir_dict_temp)?.next().is_none() {
+        return Ok(());
+    }
+
+    let violations = validate_dir(&pathdir_dict_temp)?;
+    for violation in &violations {
+        eprintln!("{violation}");
+    }
+    anyhow::ensure!(violations.is_empty(), "schema violations!");
+    Ok(())
+}
+
 /// Read the expected result in the snapshot first, then git diff
-fn shapshot_main(options: &Options, pm: &PathManager) -> Result<()> {
+fn check_snapshot<D: Dictionary>(dict: D, options: &Options, pm: &PathManager) -> Result<()> {
     delete_previous_output(pm)?;
-    make_dict(DMain, options, pm)?;
+    make_dict(dict, options, pm)?;
     check_git_diff(pm)?;
+    check_schemas(pm)?;
     Ok(())
 }